@@ -1,416 +1,458 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::byond::{
+    begin_connection, connect_with_attempt, get_auth_for_mode, resolve_server_version, AuthError,
+};
+use crate::relays::RelayState;
+use crate::servers::{Server, ServerState};
+use crate::settings::{load_settings, AuthMode};
 #[cfg(feature = "steam")]
-mod implementation {
-    use serde::{Deserialize, Serialize};
-    use std::sync::Arc;
-    use tauri::{AppHandle, Emitter, Manager};
-
-    use crate::auth::TokenStorage;
-    use crate::byond::connect_to_server_internal;
-    use crate::relays::RelayState;
-    use crate::servers::{Server, ServerState};
-    use crate::settings::{load_settings, AuthMode};
-    use crate::steam::{authenticate_with_steam, SteamState};
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    #[serde(rename_all = "snake_case")]
-    pub enum AutoConnectStatus {
-        Starting,
-        WaitingForServers,
-        WaitingForRelays,
-        ServerNotFound,
-        ServerUnavailable,
-        AuthRequired,
-        SteamLinkingRequired,
-        Connecting,
-        Connected,
-        Error,
-    }
+use crate::steam::SteamState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoConnectStatus {
+    Starting,
+    WaitingForServers,
+    WaitingForRelays,
+    ServerNotFound,
+    ServerUnavailable,
+    AuthRequired,
+    SteamLinkingRequired,
+    Connecting,
+    Connected,
+    Cancelled,
+    Error,
+}
 
-    #[derive(Debug, Clone, Serialize)]
-    pub struct AutoConnectEvent {
-        pub status: AutoConnectStatus,
-        pub server_name: String,
-        pub message: Option<String>,
-        pub linking_url: Option<String>,
-    }
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoConnectEvent {
+    pub status: AutoConnectStatus,
+    pub server_name: String,
+    pub message: Option<String>,
+    pub linking_url: Option<String>,
+}
 
-    fn emit_status(
-        handle: &AppHandle,
-        server_name: &str,
-        status: AutoConnectStatus,
-        message: Option<String>,
-        linking_url: Option<String>,
-    ) {
-        let event = AutoConnectEvent {
-            status,
-            server_name: server_name.to_string(),
-            message,
-            linking_url,
-        };
-        let _ = handle.emit("autoconnect-status", &event);
-    }
+fn emit_status(
+    handle: &AppHandle,
+    server_name: &str,
+    status: AutoConnectStatus,
+    message: Option<String>,
+    linking_url: Option<String>,
+) {
+    let event = AutoConnectEvent {
+        status,
+        server_name: server_name.to_string(),
+        message,
+        linking_url,
+    };
+    let _ = handle.emit("autoconnect-status", &event);
+}
 
-    fn find_server(servers: &[Server], server_name: &str) -> Option<Server> {
-        let normalized_name = server_name.replace('+', " ").to_lowercase();
-        servers
-            .iter()
-            .find(|s| s.name.to_lowercase() == normalized_name)
-            .cloned()
-    }
+fn find_server(servers: &[Server], server_name: &str) -> Option<Server> {
+    let normalized_name = server_name.replace('+', " ").to_lowercase();
+    servers
+        .iter()
+        .find(|s| s.name.to_lowercase() == normalized_name)
+        .cloned()
+}
 
-    fn parse_server_url(url: &str) -> Option<String> {
-        url.split(':').nth(1).map(|s| s.to_string())
-    }
+fn parse_server_url(url: &str) -> Option<String> {
+    url.split(':').nth(1).map(|s| s.to_string())
+}
 
-    async fn get_steam_access_token(handle: &AppHandle) -> Result<Option<String>, String> {
-        let steam_state = match handle.try_state::<Arc<SteamState>>() {
-            Some(state) => state,
-            None => return Err("Steam not available".to_string()),
-        };
-
-        let result = authenticate_with_steam(&steam_state, false).await?;
-
-        if result.success {
-            Ok(result.access_token)
-        } else if result.requires_linking {
-            Err(format!(
-                "LINKING_REQUIRED:{}",
-                result.linking_url.unwrap_or_default()
-            ))
-        } else {
-            Err(result
-                .error
-                .unwrap_or_else(|| "Steam authentication failed".to_string()))
-        }
+/// The status reported for an auth failure. Anything the player can't fix by
+/// logging in or linking Steam is a plain error.
+fn auth_error_status(auth_error: &AuthError) -> AutoConnectStatus {
+    match auth_error.code.as_str() {
+        "auth_required" => AutoConnectStatus::AuthRequired,
+        "steam_linking_required" => AutoConnectStatus::SteamLinkingRequired,
+        _ => AutoConnectStatus::Error,
     }
+}
 
-    async fn get_access_token_for_mode(
-        handle: &AppHandle,
-        auth_mode: AuthMode,
-    ) -> Result<(Option<String>, Option<String>), String> {
-        match auth_mode {
-            AuthMode::CmSs13 => {
-                let tokens = TokenStorage::get_tokens()?;
-                match tokens {
-                    Some(t) if !TokenStorage::is_expired() => {
-                        Ok((Some("cm_ss13".to_string()), Some(t.access_token)))
-                    }
-                    _ => Err("AUTH_REQUIRED".to_string()),
-                }
-            }
-            AuthMode::Steam => match get_steam_access_token(handle).await {
-                Ok(token) => Ok((Some("steam".to_string()), token)),
-                Err(e) => Err(e),
-            },
-            AuthMode::Byond => Ok((Some("byond".to_string()), None)),
+/// Overrides for a single auto-connect, e.g. from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct AutoConnectOptions {
+    /// Relay id to connect through instead of the fastest one.
+    pub relay: Option<String>,
+    /// Used instead of the auth mode in settings.
+    pub auth_mode: Option<AuthMode>,
+}
+
+pub async fn perform_autoconnect(
+    handle: AppHandle,
+    server_name: String,
+    options: AutoConnectOptions,
+) {
+    tracing::info!("Starting auto-connect to: {}", server_name);
+    emit_status(
+        &handle,
+        &server_name,
+        AutoConnectStatus::Starting,
+        None,
+        None,
+    );
+
+    let server_state = match handle.try_state::<Arc<ServerState>>() {
+        Some(state) => state.inner().clone(),
+        None => {
+            tracing::error!("ServerState not available");
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some("Server state not available".to_string()),
+                None,
+            );
+            return;
         }
-    }
+    };
 
-    pub async fn perform_autoconnect(handle: AppHandle, server_name: String) {
-        tracing::info!("Starting auto-connect to: {}", server_name);
+    let servers = server_state.get_servers().await;
+    if servers.is_empty() {
+        tracing::warn!("No servers available yet");
         emit_status(
             &handle,
             &server_name,
-            AutoConnectStatus::Starting,
+            AutoConnectStatus::WaitingForServers,
             None,
             None,
         );
+        emit_status(
+            &handle,
+            &server_name,
+            AutoConnectStatus::Error,
+            Some("No servers available".to_string()),
+            None,
+        );
+        return;
+    }
 
-        let server_state = match handle.try_state::<Arc<ServerState>>() {
-            Some(state) => state.inner().clone(),
-            None => {
-                tracing::error!("ServerState not available");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("Server state not available".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
-
-        let servers = server_state.get_servers().await;
-        if servers.is_empty() {
-            tracing::warn!("No servers available yet");
+    let server = match find_server(&servers, &server_name) {
+        Some(s) => s,
+        None => {
+            tracing::error!("Server not found: {}", server_name);
             emit_status(
                 &handle,
                 &server_name,
-                AutoConnectStatus::WaitingForServers,
+                AutoConnectStatus::ServerNotFound,
+                Some(format!("Server \"{}\" not found", server_name)),
                 None,
+            );
+            return;
+        }
+    };
+
+    if server.status != "available" {
+        tracing::error!(
+            "Server not available: {} (status: {})",
+            server_name,
+            server.status
+        );
+        emit_status(
+            &handle,
+            &server_name,
+            AutoConnectStatus::ServerUnavailable,
+            Some(format!(
+                "Server \"{}\" is currently unavailable",
+                server_name
+            )),
+            None,
+        );
+        return;
+    }
+
+    let port = match parse_server_url(&server.url) {
+        Some(p) => p,
+        None => {
+            tracing::error!("Could not parse server URL: {}", server.url);
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some("Invalid server configuration".to_string()),
                 None,
             );
+            return;
+        }
+    };
+
+    let version = match resolve_server_version(&handle, &server).await {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!("No BYOND version for server: {}", e);
             emit_status(
                 &handle,
                 &server_name,
                 AutoConnectStatus::Error,
-                Some("No servers available".to_string()),
+                Some(e),
                 None,
             );
             return;
         }
+    };
 
-        let server = match find_server(&servers, &server_name) {
-            Some(s) => s,
-            None => {
-                tracing::error!("Server not found: {}", server_name);
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::ServerNotFound,
-                    Some(format!("Server \"{}\" not found", server_name)),
-                    None,
-                );
-                return;
-            }
-        };
+    let settings = match load_settings(&handle) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::error!("Failed to load settings: {}", e);
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some("Failed to load settings".to_string()),
+                None,
+            );
+            return;
+        }
+    };
 
-        if server.status != "available" {
-            tracing::error!(
-                "Server not available: {} (status: {})",
-                server_name,
-                server.status
+    let source = Some("autoconnect".to_string());
+    let attempt = match begin_connection(&handle, &server_name, source.as_deref()) {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => {
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some("Connection already in progress".to_string()),
+                None,
             );
+            return;
+        }
+        Err(e) => {
+            tracing::error!("Failed to start connection: {}", e);
             emit_status(
                 &handle,
                 &server_name,
-                AutoConnectStatus::ServerUnavailable,
-                Some(format!(
-                    "Server \"{}\" is currently unavailable",
-                    server_name
-                )),
+                AutoConnectStatus::Error,
+                Some(e),
                 None,
             );
             return;
         }
+    };
 
-        let port = match parse_server_url(&server.url) {
-            Some(p) => p,
-            None => {
-                tracing::error!("Could not parse server URL: {}", server.url);
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("Invalid server configuration".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
-
-        let version = match &server.recommended_byond_version {
-            Some(v) => v.clone(),
-            None => {
-                tracing::error!("No BYOND version specified for server");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("No BYOND version specified".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
-
-        let settings = match load_settings(&handle) {
-            Ok(s) => s,
-            Err(e) => {
-                tracing::error!("Failed to load settings: {}", e);
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("Failed to load settings".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
-
-        let (access_type, access_token) =
-            match get_access_token_for_mode(&handle, settings.auth_mode).await {
-                Ok((t, tok)) => (t, tok),
-                Err(e) if e == "AUTH_REQUIRED" => {
-                    tracing::info!("CM-SS13 auth required");
-                    emit_status(
-                        &handle,
-                        &server_name,
-                        AutoConnectStatus::AuthRequired,
-                        Some("Please log in to continue".to_string()),
-                        None,
-                    );
-                    return;
-                }
-                Err(e) if e.starts_with("LINKING_REQUIRED:") => {
-                    let linking_url = e.strip_prefix("LINKING_REQUIRED:").map(|s| s.to_string());
-                    tracing::info!("Steam linking required");
-                    emit_status(
-                        &handle,
-                        &server_name,
-                        AutoConnectStatus::SteamLinkingRequired,
-                        Some("Steam account linking required".to_string()),
-                        linking_url,
-                    );
-                    return;
-                }
-                Err(e) => {
-                    tracing::error!("Auth error: {}", e);
-                    emit_status(
-                        &handle,
-                        &server_name,
-                        AutoConnectStatus::Error,
-                        Some(e),
-                        None,
-                    );
-                    return;
-                }
-            };
-
-        let relay_state = match handle.try_state::<Arc<RelayState>>() {
-            Some(state) => state.inner().clone(),
-            None => {
-                tracing::error!("RelayState not available");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("Relay state not available".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
-
+    let Ok(auth) = attempt
+        .run(get_auth_for_mode(
+            &handle,
+            options.auth_mode.unwrap_or(settings.auth_mode),
+        ))
+        .await
+    else {
+        attempt.cancelled();
         emit_status(
             &handle,
             &server_name,
-            AutoConnectStatus::WaitingForRelays,
+            AutoConnectStatus::Cancelled,
             None,
             None,
         );
-
-        let mut attempts = 0;
-        while !relay_state.all_relays_pinged().await {
-            attempts += 1;
-            if attempts >= 60 {
-                tracing::error!("Timed out waiting for relays to be pinged");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("Timed out waiting for relays".to_string()),
-                    None,
-                );
-                return;
-            }
-            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        return;
+    };
+
+    let (access_type, access_token) = match auth {
+        Ok(auth) => auth,
+        Err(auth_error) => {
+            tracing::info!("Auto-connect auth failed: {}", auth_error.message);
+            attempt.fail(&auth_error.message);
+            emit_status(
+                &handle,
+                &server_name,
+                auth_error_status(&auth_error),
+                Some(auth_error.message),
+                auth_error.linking_url,
+            );
+            return;
         }
+    };
 
-        let relay_host = match relay_state.get_selected_host().await {
-            Some(host) => host,
-            None => {
-                tracing::error!("No relay selected after pinging");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Error,
-                    Some("No relay available".to_string()),
-                    None,
-                );
-                return;
-            }
-        };
+    let relay_state = match handle.try_state::<Arc<RelayState>>() {
+        Some(state) => state.inner().clone(),
+        None => {
+            tracing::error!("RelayState not available");
+            attempt.fail("Relay state not available");
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some("Relay state not available".to_string()),
+                None,
+            );
+            return;
+        }
+    };
 
-        tracing::info!("Connecting to {} via {}", server_name, relay_host);
+    // A relay picked on the command line is used as is, without waiting
+    // for the fastest one to be found
+    if options.relay.is_none() {
         emit_status(
             &handle,
             &server_name,
-            AutoConnectStatus::Connecting,
+            AutoConnectStatus::WaitingForRelays,
             None,
             None,
         );
 
-        match connect_to_server_internal(
-            handle.clone(),
-            version,
-            relay_host,
-            port,
-            access_type,
-            access_token,
-            server_name.clone(),
-            Some("autoconnect".to_string()),
-        )
-        .await
-        {
-            Ok(result) if result.success => {
-                tracing::info!("Connection initiated successfully");
-                emit_status(
-                    &handle,
-                    &server_name,
-                    AutoConnectStatus::Connected,
-                    None,
-                    None,
-                );
-            }
-            Ok(result) => {
-                tracing::error!("Connection failed: {}", result.message);
+        let relays_ready = attempt
+            .run(async {
+                let mut attempts = 0;
+                while !relay_state.all_relays_pinged().await {
+                    attempts += 1;
+                    if attempts >= 60 {
+                        return false;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+                }
+                true
+            })
+            .await;
+
+        match relays_ready {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::error!("Timed out waiting for relays to be pinged");
+                attempt.fail("Timed out waiting for relays");
                 emit_status(
                     &handle,
                     &server_name,
                     AutoConnectStatus::Error,
-                    Some(result.message),
+                    Some("Timed out waiting for relays".to_string()),
                     None,
                 );
+                return;
             }
-            Err(e) => {
-                tracing::error!("Connection error: {}", e);
+            Err(_) => {
+                attempt.cancelled();
                 emit_status(
                     &handle,
                     &server_name,
-                    AutoConnectStatus::Error,
-                    Some(e),
+                    AutoConnectStatus::Cancelled,
+                    None,
                     None,
                 );
+                return;
             }
         }
     }
 
-    pub fn check_and_start_autoconnect(handle: AppHandle) {
-        let steam_state = match handle.try_state::<Arc<SteamState>>() {
-            Some(state) => state,
-            None => {
-                tracing::debug!("Steam not available, skipping auto-connect check");
-                return;
-            }
-        };
-
-        let launch_command = steam_state.get_launch_command_line();
-        if launch_command.is_empty() {
-            tracing::debug!("No Steam launch options");
+    let relay_host = match &options.relay {
+        Some(relay_id) => relay_state
+            .get_relay_host(relay_id)
+            .await
+            .ok_or_else(|| format!("Relay \"{}\" not found", relay_id)),
+        None => relay_state
+            .get_selected_host()
+            .await
+            .ok_or_else(|| "No relay available".to_string()),
+    };
+
+    let relay_host = match relay_host {
+        Ok(host) => host,
+        Err(e) => {
+            tracing::error!("No relay to connect through: {}", e);
+            attempt.fail(&e);
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some(e),
+                None,
+            );
             return;
         }
+    };
+
+    tracing::info!("Connecting to {} via {}", server_name, relay_host);
+    emit_status(
+        &handle,
+        &server_name,
+        AutoConnectStatus::Connecting,
+        None,
+        None,
+    );
+
+    match connect_with_attempt(
+        attempt,
+        version,
+        relay_host,
+        port,
+        access_type,
+        access_token,
+        source,
+    )
+    .await
+    {
+        Ok(result) if result.success => {
+            tracing::info!("Connection initiated successfully");
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Connected,
+                None,
+                None,
+            );
+        }
+        Ok(result) => {
+            tracing::error!("Connection failed: {}", result.message);
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some(result.message),
+                None,
+            );
+        }
+        Err(e) => {
+            tracing::error!("Connection error: {}", e);
+            emit_status(
+                &handle,
+                &server_name,
+                AutoConnectStatus::Error,
+                Some(e),
+                None,
+            );
+        }
+    }
+}
+
+/// Auto-connect to `server_name` in the background.
+pub fn start_autoconnect(handle: AppHandle, server_name: String, options: AutoConnectOptions) {
+    tauri::async_runtime::spawn(async move {
+        perform_autoconnect(handle, server_name, options).await;
+    });
+}
 
-        let server_name = launch_command.trim().to_string();
-        if server_name.is_empty() {
+#[cfg(feature = "steam")]
+pub fn check_and_start_autoconnect(handle: AppHandle) {
+    let steam_state = match handle.try_state::<Arc<SteamState>>() {
+        Some(state) => state,
+        None => {
+            tracing::debug!("Steam not available, skipping auto-connect check");
             return;
         }
+    };
 
-        tracing::info!("Steam launch option detected: {}", server_name);
+    let launch_command = steam_state.get_launch_command_line();
+    if launch_command.is_empty() {
+        tracing::debug!("No Steam launch options");
+        return;
+    }
 
-        // Spawn the auto-connect task
-        tauri::async_runtime::spawn(async move {
-            perform_autoconnect(handle, server_name).await;
-        });
+    let server_name = launch_command.trim().to_string();
+    if server_name.is_empty() {
+        return;
     }
-}
 
-#[cfg(feature = "steam")]
-pub use implementation::check_and_start_autoconnect;
+    tracing::info!("Steam launch option detected: {}", server_name);
+
+    start_autoconnect(handle, server_name, AutoConnectOptions::default());
+}
 
 #[cfg(not(feature = "steam"))]
-pub fn check_and_start_autoconnect(_handle: tauri::AppHandle) {
-    tracing::debug!("Steam not compiled in, auto-connect disabled");
+pub fn check_and_start_autoconnect(_handle: AppHandle) {
+    tracing::debug!("Steam not compiled in, Steam launch options not checked");
 }
@@ -1,646 +0,0 @@
-use serde::{Deserialize, Serialize};
-use std::fs;
-use std::io;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tauri::{AppHandle, Manager};
-
-use crate::auth::TokenStorage;
-use crate::relays::RelayState;
-use crate::servers::ServerState;
-use crate::settings::{load_settings, AuthMode};
-
-#[cfg(target_os = "windows")]
-use crate::control_server::ControlServer;
-#[cfg(target_os = "windows")]
-use crate::presence::{ConnectionParams, PresenceManager};
-#[cfg(target_os = "windows")]
-use std::process::Command;
-#[cfg(target_os = "windows")]
-use tauri::Emitter;
-
-#[cfg(feature = "steam")]
-use crate::steam::{authenticate_with_steam, SteamState};
-
-static CONNECTING: AtomicBool = AtomicBool::new(false);
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ByondVersionInfo {
-    pub version: String,
-    pub installed: bool,
-    pub path: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct AuthError {
-    pub code: String,
-    pub message: String,
-    pub linking_url: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ConnectionResult {
-    pub success: bool,
-    pub message: String,
-    pub auth_error: Option<AuthError>,
-}
-
-/// Build a BYOND connection URL with optional auth and launcher port.
-pub fn build_connect_url(
-    host: &str,
-    port: &str,
-    access_type: Option<&str>,
-    access_token: Option<&str>,
-    launcher_port: Option<&str>,
-) -> String {
-    let mut query_params = Vec::new();
-    if let (Some(access_type), Some(token)) = (access_type, access_token) {
-        query_params.push(format!("{}={}", access_type, token));
-    }
-    if let Some(port) = launcher_port {
-        query_params.push(format!("launcher_port={}", port));
-    }
-
-    if query_params.is_empty() {
-        format!("byond://{}:{}", host, port)
-    } else {
-        format!("byond://{}:{}?{}", host, port, query_params.join("&"))
-    }
-}
-
-fn get_byond_base_dir(_app: &AppHandle) -> Result<PathBuf, String> {
-    let local_data = dirs::data_local_dir()
-        .ok_or("Failed to get local data directory")?
-        .join("com.cm-ss13.launcher");
-
-    Ok(local_data.join("byond"))
-}
-
-fn get_byond_version_dir(app: &AppHandle, version: &str) -> Result<PathBuf, String> {
-    let base = get_byond_base_dir(app)?;
-    Ok(base.join(version))
-}
-
-#[cfg(target_os = "windows")]
-fn get_dreamseeker_path(app: &AppHandle, version: &str) -> Result<PathBuf, String> {
-    let version_dir = get_byond_version_dir(app, version)?;
-    Ok(version_dir
-        .join("byond")
-        .join("bin")
-        .join("dreamseeker.exe"))
-}
-
-#[cfg(not(target_os = "windows"))]
-fn get_dreamseeker_path(_app: &AppHandle, _version: &str) -> Result<PathBuf, String> {
-    Err("BYOND is only natively supported on Windows".to_string())
-}
-
-#[tauri::command]
-pub async fn check_byond_version(
-    app: AppHandle,
-    version: String,
-) -> Result<ByondVersionInfo, String> {
-    tracing::debug!("Checking BYOND version: {}", version);
-    let dreamseeker_path = get_dreamseeker_path(&app, &version)?;
-    let installed = dreamseeker_path.exists();
-
-    Ok(ByondVersionInfo {
-        version: version.clone(),
-        installed,
-        path: if installed {
-            Some(dreamseeker_path.to_string_lossy().to_string())
-        } else {
-            None
-        },
-    })
-}
-
-fn get_byond_download_url(version: &str) -> Result<String, String> {
-    let parts: Vec<&str> = version.split('.').collect();
-    if parts.len() != 2 {
-        return Err(format!("Invalid BYOND version format: {}", version));
-    }
-
-    let major = parts[0];
-
-    Ok(format!(
-        "https://www.byond.com/download/build/{}/{}_byond.zip",
-        major, version
-    ))
-}
-
-#[tauri::command]
-pub async fn install_byond_version(
-    app: AppHandle,
-    version: String,
-) -> Result<ByondVersionInfo, String> {
-    let existing = check_byond_version(app.clone(), version.clone()).await?;
-    if existing.installed {
-        tracing::debug!("BYOND version {} already installed", version);
-        return Ok(existing);
-    }
-
-    tracing::info!("Installing BYOND version: {}", version);
-    let download_url = get_byond_download_url(&version)?;
-    let version_dir = get_byond_version_dir(&app, &version)?;
-
-    fs::create_dir_all(&version_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
-
-    let zip_path = version_dir.join("byond.zip");
-
-    let response = reqwest::get(&download_url)
-        .await
-        .map_err(|e| format!("Failed to download BYOND: {}", e))?;
-
-    if !response.status().is_success() {
-        return Err(format!(
-            "Failed to download BYOND version {}: HTTP {}",
-            version,
-            response.status()
-        ));
-    }
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read download: {}", e))?;
-
-    fs::write(&zip_path, &bytes).map_err(|e| format!("Failed to save download: {}", e))?;
-
-    let file = fs::File::open(&zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
-
-    let mut archive =
-        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
-
-    for i in 0..archive.len() {
-        let mut file = archive
-            .by_index(i)
-            .map_err(|e| format!("Failed to read zip entry: {}", e))?;
-
-        let outpath = match file.enclosed_name() {
-            Some(path) => version_dir.join(path),
-            None => continue,
-        };
-
-        if file.name().ends_with('/') {
-            fs::create_dir_all(&outpath)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        } else {
-            if let Some(parent) = outpath.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)
-                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
-                }
-            }
-            let mut outfile =
-                fs::File::create(&outpath).map_err(|e| format!("Failed to create file: {}", e))?;
-            io::copy(&mut file, &mut outfile)
-                .map_err(|e| format!("Failed to extract file: {}", e))?;
-        }
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            if let Some(mode) = file.unix_mode() {
-                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode)).ok();
-            }
-        }
-    }
-
-    fs::remove_file(&zip_path).ok();
-
-    tracing::info!("BYOND version {} installed successfully", version);
-
-    check_byond_version(app, version).await
-}
-
-/// Internal function for connecting with explicit auth params.
-/// Used by autoconnect and the simplified connect_to_server command.
-pub async fn connect_to_server_internal(
-    app: AppHandle,
-    version: String,
-    host: String,
-    port: String,
-    access_type: Option<String>,
-    access_token: Option<String>,
-    server_name: String,
-    source: Option<String>,
-) -> Result<ConnectionResult, String> {
-    let source_str = source.as_deref().unwrap_or("unknown");
-
-    if CONNECTING.swap(true, Ordering::SeqCst) {
-        tracing::warn!(
-            "[connect_to_server] BLOCKED duplicate connection attempt, source={} server={}",
-            source_str,
-            server_name
-        );
-        return Ok(ConnectionResult {
-            success: false,
-            message: "Connection already in progress".to_string(),
-            auth_error: None,
-        });
-    }
-
-    tracing::info!(
-        "[connect_to_server] source={} server={} version={}",
-        source_str,
-        server_name,
-        version
-    );
-
-    let result = connect_to_server_impl(
-        app,
-        version,
-        host,
-        port,
-        access_type,
-        access_token,
-        server_name,
-        source,
-    )
-    .await;
-
-    CONNECTING.store(false, Ordering::SeqCst);
-    result
-}
-
-async fn get_auth_for_connection(
-    app: &AppHandle,
-) -> Result<(Option<String>, Option<String>), AuthError> {
-    let settings = load_settings(app).map_err(|e| AuthError {
-        code: "settings_error".to_string(),
-        message: e,
-        linking_url: None,
-    })?;
-
-    match settings.auth_mode {
-        AuthMode::CmSs13 => {
-            let tokens = TokenStorage::get_tokens().map_err(|e| AuthError {
-                code: "token_error".to_string(),
-                message: e,
-                linking_url: None,
-            })?;
-
-            match tokens {
-                Some(t) if !TokenStorage::is_expired() => {
-                    Ok((Some("cm_ss13".to_string()), Some(t.access_token)))
-                }
-                _ => Err(AuthError {
-                    code: "auth_required".to_string(),
-                    message: "Please log in to CM-SS13".to_string(),
-                    linking_url: None,
-                }),
-            }
-        }
-        AuthMode::Steam => {
-            #[cfg(feature = "steam")]
-            {
-                let steam_state = app
-                    .try_state::<Arc<SteamState>>()
-                    .ok_or_else(|| AuthError {
-                        code: "steam_unavailable".to_string(),
-                        message: "Steam is not available".to_string(),
-                        linking_url: None,
-                    })?;
-
-                let result = authenticate_with_steam(&steam_state, false)
-                    .await
-                    .map_err(|e| AuthError {
-                        code: "steam_error".to_string(),
-                        message: e,
-                        linking_url: None,
-                    })?;
-
-                if result.success {
-                    Ok((Some("steam".to_string()), result.access_token))
-                } else if result.requires_linking {
-                    Err(AuthError {
-                        code: "steam_linking_required".to_string(),
-                        message: "Steam account linking required".to_string(),
-                        linking_url: result.linking_url,
-                    })
-                } else {
-                    Err(AuthError {
-                        code: "steam_auth_failed".to_string(),
-                        message: result
-                            .error
-                            .unwrap_or_else(|| "Steam authentication failed".to_string()),
-                        linking_url: None,
-                    })
-                }
-            }
-
-            #[cfg(not(feature = "steam"))]
-            {
-                Err(AuthError {
-                    code: "steam_unavailable".to_string(),
-                    message: "Steam support not compiled".to_string(),
-                    linking_url: None,
-                })
-            }
-        }
-        AuthMode::Byond => {
-            if !check_byond_pager_running() {
-                return Err(AuthError {
-                    code: "byond_pager_not_running".to_string(),
-                    message: "BYOND pager is not running. Please open BYOND and log in before connecting.".to_string(),
-                    linking_url: None,
-                });
-            }
-            Ok((Some("byond".to_string()), None))
-        }
-    }
-}
-
-#[tauri::command]
-pub async fn connect_to_server(
-    app: AppHandle,
-    server_name: String,
-    source: Option<String>,
-) -> Result<ConnectionResult, String> {
-    let source_str = source.as_deref().unwrap_or("unknown");
-
-    let server_state = app
-        .try_state::<Arc<ServerState>>()
-        .ok_or("Server state not available")?;
-    let servers = server_state.get_servers().await;
-    let server = servers
-        .iter()
-        .find(|s| s.name == server_name)
-        .ok_or_else(|| format!("Server '{}' not found", server_name))?
-        .clone();
-
-    let version = server
-        .recommended_byond_version
-        .ok_or("Server has no recommended BYOND version")?;
-
-    let port = server
-        .url
-        .split(':')
-        .nth(1)
-        .ok_or("Invalid server URL format")?
-        .to_string();
-
-    let relay_state = app
-        .try_state::<Arc<RelayState>>()
-        .ok_or("Relay state not available")?;
-    let host = relay_state
-        .get_selected_host()
-        .await
-        .ok_or("No relay selected")?;
-
-    let (access_type, access_token) = match get_auth_for_connection(&app).await {
-        Ok((t, tok)) => (t, tok),
-        Err(auth_error) => {
-            return Ok(ConnectionResult {
-                success: false,
-                message: auth_error.message.clone(),
-                auth_error: Some(auth_error),
-            });
-        }
-    };
-
-    tracing::info!(
-        "[connect_to_server] source={} server={} version={} host={}",
-        source_str,
-        server_name,
-        version,
-        host
-    );
-
-    connect_to_server_internal(
-        app,
-        version,
-        host,
-        port,
-        access_type,
-        access_token,
-        server_name,
-        source,
-    )
-    .await
-}
-
-async fn connect_to_server_impl(
-    app: AppHandle,
-    version: String,
-    host: String,
-    port: String,
-    access_type: Option<String>,
-    access_token: Option<String>,
-    server_name: String,
-    source: Option<String>,
-) -> Result<ConnectionResult, String> {
-    let version_info = install_byond_version(app.clone(), version.clone()).await?;
-
-    if !version_info.installed {
-        let msg = format!("Failed to install BYOND version {}", version);
-        tracing::error!("{}", msg);
-        return Err(msg);
-    }
-
-    let dreamseeker_path = version_info.path.ok_or("DreamSeeker path not found")?;
-
-    #[cfg(target_os = "windows")]
-    {
-        if let Some(control_server) = app.try_state::<ControlServer>() {
-            control_server.reset_connected_flag();
-        }
-
-        if source.as_deref() != Some("control_server_restart") {
-            app.emit("game-connecting", &server_name).ok();
-        }
-
-        let control_port = app.try_state::<ControlServer>().map(|s| s.port.to_string());
-
-        let connect_url = build_connect_url(
-            &host,
-            &port,
-            access_type.as_deref(),
-            access_token.as_deref(),
-            control_port.as_deref(),
-        );
-
-        // Set a unique WebView2 user data folder to avoid conflicts with the system BYOND pager.
-        // When the BYOND pager is running, it locks the default WebView2 user data directory,
-        // preventing our DreamSeeker from using WebView2. Using a separate folder resolves this.
-        let webview2_data_dir = get_byond_base_dir(&app)?.join("webview2_data");
-
-        let child = Command::new(&dreamseeker_path)
-            .arg(&connect_url)
-            .env("WEBVIEW2_USER_DATA_FOLDER", &webview2_data_dir)
-            .spawn()
-            .map_err(|e| format!("Failed to launch DreamSeeker: {}", e))?;
-
-        if let Some(manager) = app.try_state::<Arc<PresenceManager>>() {
-            manager.set_last_connection_params(ConnectionParams {
-                version: version.clone(),
-                host: host.clone(),
-                port: port.clone(),
-                access_type,
-                access_token,
-                server_name: server_name.clone(),
-            });
-
-            manager.start_game_session(
-                server_name,
-                "https://db.cm-ss13.com/api/Round".to_string(),
-                child,
-            );
-        }
-
-        Ok(ConnectionResult {
-            success: true,
-            message: format!("Connecting to {} with BYOND {}", host, version),
-            auth_error: None,
-        })
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Suppress unused warnings
-        let _ = (
-            dreamseeker_path,
-            host,
-            port,
-            server_name,
-            access_type,
-            access_token,
-            source,
-        );
-        Err("BYOND is only natively supported on Windows".to_string())
-    }
-}
-
-#[tauri::command]
-pub async fn list_installed_byond_versions(
-    app: AppHandle,
-) -> Result<Vec<ByondVersionInfo>, String> {
-    let base_dir = get_byond_base_dir(&app)?;
-
-    if !base_dir.exists() {
-        return Ok(vec![]);
-    }
-
-    let mut versions = Vec::new();
-
-    let entries =
-        fs::read_dir(&base_dir).map_err(|e| format!("Failed to read BYOND directory: {}", e))?;
-
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            if let Some(version_name) = path.file_name().and_then(|n| n.to_str()) {
-                let info = check_byond_version(app.clone(), version_name.to_string()).await?;
-                if info.installed {
-                    versions.push(info);
-                }
-            }
-        }
-    }
-
-    Ok(versions)
-}
-
-#[tauri::command]
-pub async fn delete_byond_version(app: AppHandle, version: String) -> Result<bool, String> {
-    let version_dir = get_byond_version_dir(&app, &version)?;
-
-    if version_dir.exists() {
-        tracing::info!("Deleting BYOND version: {}", version);
-        fs::remove_dir_all(&version_dir)
-            .map_err(|e| format!("Failed to delete BYOND version: {}", e))?;
-        Ok(true)
-    } else {
-        Ok(false)
-    }
-}
-
-fn check_byond_pager_running() -> bool {
-    #[cfg(target_os = "windows")]
-    {
-        use sysinfo::System;
-
-        let s = System::new_all();
-        s.processes().values().any(|p| {
-            p.name()
-                .to_str()
-                .map(|name| name.eq_ignore_ascii_case("byond.exe"))
-                .unwrap_or(false)
-        })
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        false
-    }
-}
-
-#[tauri::command]
-pub async fn is_byond_pager_running() -> Result<bool, String> {
-    Ok(check_byond_pager_running())
-}
-#[tauri::command]
-pub fn is_dev_mode() -> bool {
-    cfg!(feature = "dev")
-}
-
-#[tauri::command]
-pub async fn connect_to_url(
-    app: AppHandle,
-    url: String,
-    version: String,
-    source: Option<String>,
-) -> Result<ConnectionResult, String> {
-    #[cfg(not(feature = "dev"))]
-    {
-        let _ = (app, url, version, source);
-        return Err("Dev mode is not enabled".to_string());
-    }
-
-    #[cfg(feature = "dev")]
-    {
-        let url = url.strip_prefix("byond://").unwrap_or(&url).to_string();
-
-        let parts: Vec<&str> = url.split(':').collect();
-        if parts.len() != 2 {
-            return Err("Invalid URL format. Expected 'host:port'".to_string());
-        }
-
-        let host = parts[0].to_string();
-        let port = parts[1].to_string();
-
-        // Get auth
-        let (access_type, access_token) = match get_auth_for_connection(&app).await {
-            Ok((t, tok)) => (t, tok),
-            Err(auth_error) => {
-                return Ok(ConnectionResult {
-                    success: false,
-                    message: auth_error.message.clone(),
-                    auth_error: Some(auth_error),
-                });
-            }
-        };
-
-        tracing::info!(
-            "[connect_to_url] dev mode connection to {}:{} version={}",
-            host,
-            port,
-            version
-        );
-
-        connect_to_server_internal(
-            app,
-            version,
-            host,
-            port,
-            access_type,
-            access_token,
-            format!("Dev Server ({})", url),
-            source,
-        )
-        .await
-    }
-}
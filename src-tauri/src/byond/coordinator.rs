@@ -0,0 +1,135 @@
+//! Deduplicates concurrent installs of the same BYOND version
+
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use super::install::CancelFlag;
+use super::{ByondVersion, ByondVersionInfo};
+
+type InstallFuture = Shared<BoxFuture<'static, Result<ByondVersionInfo, String>>>;
+
+struct InFlight {
+    future: InstallFuture,
+    waiters: usize,
+    cancel: CancelFlag,
+}
+
+/// Tracks in-flight installs by version. Autoconnect, `connect_to_server` and
+/// manual installs can all ask for the same version at once; only the first
+/// caller starts an install and everyone else waits on its result.
+#[derive(Default)]
+pub struct InstallCoordinator {
+    in_flight: Mutex<HashMap<ByondVersion, InFlight>>,
+}
+
+/// Held by each caller waiting on an install. When the last one is dropped
+/// before the install finishes, e.g. because a background prefetch was
+/// cancelled, the install is told to stop.
+struct Waiter<'a> {
+    coordinator: &'a InstallCoordinator,
+    version: ByondVersion,
+    future: InstallFuture,
+}
+
+impl Drop for Waiter<'_> {
+    fn drop(&mut self) {
+        let mut in_flight = self.coordinator.in_flight.lock().unwrap();
+        let Some(entry) = in_flight.get_mut(&self.version) else {
+            return;
+        };
+        if !entry.future.ptr_eq(&self.future) {
+            return;
+        }
+
+        entry.waiters -= 1;
+        if entry.waiters > 0 {
+            return;
+        }
+
+        if entry.future.peek().is_some() {
+            in_flight.remove(&self.version);
+        } else {
+            // Keep the entry until the install actually stops, so nobody
+            // starts a second one writing to the same files in the meantime.
+            tracing::info!(
+                "Cancelling BYOND {} install, nothing is waiting on it",
+                self.version
+            );
+            entry.cancel.cancel();
+        }
+    }
+}
+
+impl InstallCoordinator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `start()` for `version` unless an install of it is already running,
+    /// in which case wait for that one instead. The install runs as its own
+    /// task and is only cancelled once every caller waiting on it has gone.
+    pub async fn install<F, Fut>(
+        &self,
+        version: ByondVersion,
+        start: F,
+    ) -> Result<ByondVersionInfo, String>
+    where
+        F: FnOnce(CancelFlag) -> Fut,
+        Fut: Future<Output = Result<ByondVersionInfo, String>> + Send + 'static,
+    {
+        let future = loop {
+            let stale = {
+                let mut in_flight = self.in_flight.lock().unwrap();
+                match in_flight.get_mut(&version) {
+                    Some(entry) if entry.cancel.is_cancelled() => entry.future.clone(),
+                    Some(entry) => {
+                        tracing::info!("Waiting on in-flight install of BYOND {}", version);
+                        entry.waiters += 1;
+                        break entry.future.clone();
+                    }
+                    None => {
+                        let cancel = CancelFlag::default();
+                        let task = tauri::async_runtime::spawn(start(cancel.clone()));
+                        let future = async move {
+                            task.await
+                                .map_err(|e| format!("Install task failed: {}", e))?
+                        }
+                        .boxed()
+                        .shared();
+
+                        in_flight.insert(
+                            version,
+                            InFlight {
+                                future: future.clone(),
+                                waiters: 1,
+                                cancel,
+                            },
+                        );
+                        break future;
+                    }
+                }
+            };
+
+            // A cancelled install is still winding down. Let it finish before
+            // starting a fresh one.
+            stale.clone().await.ok();
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight
+                .get(&version)
+                .is_some_and(|entry| entry.future.ptr_eq(&stale))
+            {
+                in_flight.remove(&version);
+            }
+        };
+
+        let _waiter = Waiter {
+            coordinator: self,
+            version,
+            future: future.clone(),
+        };
+
+        future.await
+    }
+}
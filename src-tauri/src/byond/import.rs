@@ -0,0 +1,158 @@
+//! Registering BYOND from a local archive or an existing installation
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::install::CancelFlag;
+use super::ByondVersion;
+use crate::launcher::dreamseeker_exe;
+
+/// Suffix of the archives byond.com serves, e.g. `516.1667_byond.zip`.
+const ARCHIVE_SUFFIX: &str = "_byond.zip";
+/// Where DreamSeeker sits relative to an archive's root.
+const ARCHIVE_DREAMSEEKER: &str = "byond/bin/dreamseeker.exe";
+
+pub enum ImportSource {
+    Archive(PathBuf),
+    Directory(PathBuf),
+}
+
+impl ImportSource {
+    pub fn from_path(path: PathBuf) -> Result<Self, String> {
+        if path.is_dir() {
+            Ok(Self::Directory(path))
+        } else if path.is_file() {
+            Ok(Self::Archive(path))
+        } else {
+            Err(format!("{} does not exist", path.display()))
+        }
+    }
+}
+
+/// The version in a byond.com archive's file name.
+pub fn version_from_archive_name(path: &Path) -> Option<ByondVersion> {
+    path.file_name()?
+        .to_str()?
+        .strip_suffix(ARCHIVE_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Check that `zip_path` is laid out like a byond.com archive.
+pub fn validate_archive(zip_path: &Path) -> Result<(), String> {
+    let file = fs::File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+    let archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+
+    // Matched exactly, as installs are checked for this path case-sensitively
+    // on Linux
+    let has_dreamseeker = archive.file_names().any(|name| name == ARCHIVE_DREAMSEEKER);
+
+    if has_dreamseeker {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is not a BYOND archive: it has no {}",
+            zip_path.display(),
+            ARCHIVE_DREAMSEEKER
+        ))
+    }
+}
+
+/// Find the BYOND root (the directory holding `bin/`) for a directory the
+/// player picked. Accepts either the root itself, as with a system install,
+/// or an extracted archive with the root under `byond/`.
+fn find_install_root(dir: &Path) -> Result<PathBuf, String> {
+    let extracted = dir.join("byond");
+    [dir.to_path_buf(), extracted]
+        .into_iter()
+        .find(|root| root.join("bin").join("dreamseeker.exe").is_file())
+        .ok_or_else(|| format!("No BYOND installation found in {}", dir.display()))
+}
+
+fn copy_dir(from: &Path, to: &Path, cancel: &CancelFlag) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let entries = fs::read_dir(from).map_err(|e| format!("Failed to read directory: {}", e))?;
+    for entry in entries {
+        cancel.check()?;
+
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read file type: {}", e))?;
+        let target = to.join(entry.file_name());
+
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target, cancel)?;
+        } else if file_type.is_file() {
+            fs::copy(entry.path(), &target).map_err(|e| format!("Failed to copy file: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copy an existing installation into `staging_dir`, laid out the same way
+/// as an extracted archive. Blocking.
+pub fn copy_installation(
+    dir: &Path,
+    staging_dir: &Path,
+    cancel: &CancelFlag,
+) -> Result<(), String> {
+    let root = find_install_root(dir)?;
+    copy_dir(&root, &staging_dir.join("byond"), cancel)?;
+
+    if dreamseeker_exe(staging_dir).is_file() {
+        Ok(())
+    } else {
+        Err("Copied BYOND installation is missing DreamSeeker".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    fn write_zip(names: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("import-{}.zip", uuid::Uuid::new_v4()));
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for name in names {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"byond").unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn accepts_a_byond_archive() {
+        let path = write_zip(&["byond/bin/byond.exe", "byond/bin/dreamseeker.exe"]);
+        assert!(validate_archive(&path).is_ok());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn rejects_dreamseeker_with_other_casing() {
+        let path = write_zip(&["BYOND/bin/DreamSeeker.exe"]);
+        assert!(validate_archive(&path).is_err());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn rejects_an_archive_without_dreamseeker() {
+        let path = write_zip(&["bin/dreamseeker.exe", "byond/readme.txt"]);
+        assert!(validate_archive(&path).is_err());
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn reads_version_from_archive_name() {
+        let version = version_from_archive_name(Path::new("/tmp/516.1667_byond.zip"));
+        assert_eq!(version, "516.1667".parse().ok());
+        assert_eq!(version_from_archive_name(Path::new("516.1667.zip")), None);
+        assert_eq!(version_from_archive_name(Path::new("../x_byond.zip")), None);
+    }
+}
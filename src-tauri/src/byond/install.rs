@@ -0,0 +1,436 @@
+//! Downloading and unpacking BYOND release archives
+
+use reqwest::header::{HeaderName, CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::io::AsyncWriteExt;
+
+use super::ByondVersion;
+
+/// Name of the downloaded archive inside a version directory.
+pub const ARCHIVE_FILE: &str = "byond.zip";
+
+const STAGING_DIR: &str = ".staging";
+const PROGRESS_EVENT: &str = "byond-install-progress";
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Tells a running install to stop at the next chunk or file it handles.
+#[derive(Debug, Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err("BYOND install was cancelled".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallPhase {
+    Download,
+    Extract,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallProgress {
+    pub version: ByondVersion,
+    pub phase: InstallPhase,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub files_extracted: usize,
+    pub total_files: usize,
+}
+
+/// Emits `byond-install-progress` events, throttled so a fast download
+/// doesn't flood the frontend with one event per chunk.
+pub struct ProgressReporter {
+    app: AppHandle,
+    progress: InstallProgress,
+    last_emit: Option<Instant>,
+}
+
+impl ProgressReporter {
+    pub fn new(app: AppHandle, version: ByondVersion) -> Self {
+        Self {
+            app,
+            progress: InstallProgress {
+                version,
+                phase: InstallPhase::Download,
+                downloaded_bytes: 0,
+                total_bytes: None,
+                files_extracted: 0,
+                total_files: 0,
+            },
+            last_emit: None,
+        }
+    }
+
+    pub fn set_phase(&mut self, phase: InstallPhase) {
+        self.progress.phase = phase;
+        self.emit(true);
+    }
+
+    fn emit(&mut self, force: bool) {
+        let now = Instant::now();
+        if !force {
+            if let Some(last) = self.last_emit {
+                if now.duration_since(last) < PROGRESS_EMIT_INTERVAL {
+                    return;
+                }
+            }
+        }
+
+        self.last_emit = Some(now);
+        self.app.emit(PROGRESS_EVENT, &self.progress).ok();
+    }
+}
+
+/// Validators for a partially downloaded archive, stored next to it so an
+/// interrupted download can be continued with a `Range` request.
+#[derive(Debug, Serialize, Deserialize)]
+struct PartialDownload {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartialDownload {
+    fn from_response(url: &str, response: &reqwest::Response) -> Self {
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string())
+        };
+
+        Self {
+            url: url.to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    /// The value to send in `If-Range`. Weak ETags can't be used for ranges.
+    fn validator(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+}
+
+fn partial_meta_path(zip_path: &Path) -> PathBuf {
+    let mut path = zip_path.as_os_str().to_owned();
+    path.push(".meta");
+    PathBuf::from(path)
+}
+
+fn load_partial(zip_path: &Path) -> Option<PartialDownload> {
+    let contents = fs::read_to_string(partial_meta_path(zip_path)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_partial(zip_path: &Path, partial: &PartialDownload) {
+    match serde_json::to_string(partial) {
+        Ok(contents) => {
+            if let Err(e) = fs::write(partial_meta_path(zip_path), contents) {
+                tracing::warn!("Failed to save partial download info: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize partial download info: {}", e),
+    }
+}
+
+/// Remove a downloaded archive along with any partial download info.
+pub fn discard_archive(zip_path: &Path) {
+    fs::remove_file(zip_path).ok();
+    fs::remove_file(partial_meta_path(zip_path)).ok();
+}
+
+/// Parse the complete length out of a `Content-Range: bytes a-b/total` header.
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+async fn request_archive(
+    client: &reqwest::Client,
+    url: &str,
+    resume: Option<(u64, &str)>,
+) -> Result<reqwest::Response, String> {
+    let mut request = client.get(url);
+    if let Some((offset, validator)) = resume {
+        request = request
+            .header(RANGE, format!("bytes={}-", offset))
+            .header(IF_RANGE, validator);
+    }
+
+    request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download BYOND: {}", e))
+}
+
+/// Stream `url` to `zip_path` chunk by chunk, reporting progress as it goes.
+/// A partial archive left over from an interrupted download is continued
+/// when the server still has the same file, otherwise it is started over.
+/// Only interrupted downloads are resumed: a cancelled one returns an error
+/// and the caller discards the partial archive.
+pub async fn download_archive(
+    url: &str,
+    zip_path: &Path,
+    reporter: &mut ProgressReporter,
+    cancel: &CancelFlag,
+) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    let existing_len = fs::metadata(zip_path).map(|m| m.len()).unwrap_or(0);
+    let partial = load_partial(zip_path).filter(|p| p.url == url);
+    let resume = match partial.as_ref().and_then(|p| p.validator()) {
+        Some(validator) if existing_len > 0 => Some((existing_len, validator)),
+        _ => None,
+    };
+
+    if resume.is_some() {
+        tracing::info!(
+            "Resuming BYOND {} download from {} bytes",
+            reporter.progress.version,
+            existing_len
+        );
+    }
+
+    let mut response = request_archive(&client, url, resume).await?;
+
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        tracing::warn!("Server rejected resume range, restarting download");
+        response = request_archive(&client, url, None).await?;
+    }
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to download BYOND version {}: HTTP {}",
+            reporter.progress.version,
+            response.status()
+        ));
+    }
+
+    let resumed = resume.is_some() && response.status() == StatusCode::PARTIAL_CONTENT;
+    if resume.is_some() && !resumed {
+        tracing::info!("Server sent the full archive, restarting download");
+    }
+
+    let mut file = if resumed {
+        reporter.progress.downloaded_bytes = existing_len;
+        reporter.progress.total_bytes = content_range_total(&response)
+            .or_else(|| response.content_length().map(|len| len + existing_len));
+
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(zip_path)
+            .await
+            .map_err(|e| format!("Failed to open partial download: {}", e))?
+    } else {
+        reporter.progress.downloaded_bytes = 0;
+        reporter.progress.total_bytes = response.content_length();
+
+        let partial = PartialDownload::from_response(url, &response);
+        if partial.validator().is_some() {
+            save_partial(zip_path, &partial);
+        } else {
+            fs::remove_file(partial_meta_path(zip_path)).ok();
+        }
+
+        tokio::fs::File::create(zip_path)
+            .await
+            .map_err(|e| format!("Failed to create download file: {}", e))?
+    };
+
+    reporter.emit(true);
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read download: {}", e))?
+    {
+        cancel.check()?;
+
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to save download: {}", e))?;
+
+        reporter.progress.downloaded_bytes += chunk.len() as u64;
+        reporter.emit(false);
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to save download: {}", e))?;
+
+    reporter.emit(true);
+
+    if let Some(total) = reporter.progress.total_bytes {
+        if reporter.progress.downloaded_bytes != total {
+            return Err(format!(
+                "Download incomplete: received {} of {} bytes",
+                reporter.progress.downloaded_bytes, total
+            ));
+        }
+    }
+
+    fs::remove_file(partial_meta_path(zip_path)).ok();
+
+    Ok(())
+}
+
+/// Unpack `zip_path` into `dest_dir`. This does blocking IO, so callers on the
+/// async runtime should run it through `spawn_blocking`.
+pub fn extract_archive(
+    zip_path: &Path,
+    dest_dir: &Path,
+    reporter: &mut ProgressReporter,
+    cancel: &CancelFlag,
+) -> Result<(), String> {
+    let file = fs::File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+
+    reporter.progress.total_files = archive.len();
+    reporter.set_phase(InstallPhase::Extract);
+
+    for i in 0..archive.len() {
+        cancel.check()?;
+
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+
+        let outpath = match file.enclosed_name() {
+            Some(path) => dest_dir.join(path),
+            None => continue,
+        };
+
+        if file.name().ends_with('/') {
+            fs::create_dir_all(&outpath)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        } else {
+            if let Some(parent) = outpath.parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)
+                        .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+                }
+            }
+            let mut outfile =
+                fs::File::create(&outpath).map_err(|e| format!("Failed to create file: {}", e))?;
+            io::copy(&mut file, &mut outfile)
+                .map_err(|e| format!("Failed to extract file: {}", e))?;
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode)).ok();
+            }
+        }
+
+        reporter.progress.files_extracted = i + 1;
+        reporter.emit(false);
+    }
+
+    reporter.emit(true);
+
+    Ok(())
+}
+
+/// A fresh directory under the base dir to extract `version` into before it
+/// is moved into place.
+pub fn new_staging_dir(base_dir: &Path, version: ByondVersion) -> PathBuf {
+    base_dir
+        .join(STAGING_DIR)
+        .join(format!("{}-{}", version, uuid::Uuid::new_v4()))
+}
+
+/// Replace `version_dir` with a fully extracted staging directory.
+pub fn promote_staging_dir(staging_dir: &Path, version_dir: &Path) -> Result<(), String> {
+    if version_dir.exists() {
+        fs::remove_dir_all(version_dir)
+            .map_err(|e| format!("Failed to clear previous install: {}", e))?;
+    }
+
+    fs::rename(staging_dir, version_dir)
+        .map_err(|e| format!("Failed to move install into place: {}", e))
+}
+
+/// Whether `version_dir` holds nothing but a partial archive we can resume.
+fn is_resumable_download(version_dir: &Path) -> bool {
+    if !partial_meta_path(&version_dir.join(ARCHIVE_FILE)).exists() {
+        return false;
+    }
+
+    let meta_name = format!("{}.meta", ARCHIVE_FILE);
+    fs::read_dir(version_dir)
+        .map(|entries| {
+            entries.flatten().all(|entry| {
+                let name = entry.file_name();
+                name == ARCHIVE_FILE || name == meta_name.as_str()
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Remove leftover staging directories, and archives that were either never
+/// cleaned up after an install or can't be resumed.
+pub fn sweep_stale_files(base_dir: &Path) {
+    let staging_root = base_dir.join(STAGING_DIR);
+    if staging_root.exists() {
+        match fs::remove_dir_all(&staging_root) {
+            Ok(()) => tracing::info!("Removed stale BYOND staging directories"),
+            Err(e) => tracing::warn!("Failed to remove BYOND staging directories: {}", e),
+        }
+    }
+
+    let entries = match fs::read_dir(base_dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let version_dir = entry.path();
+        let zip_path = version_dir.join(ARCHIVE_FILE);
+
+        if !zip_path.exists() || is_resumable_download(&version_dir) {
+            continue;
+        }
+
+        tracing::info!("Removing leftover BYOND archive: {:?}", zip_path);
+        discard_archive(&zip_path);
+    }
+}
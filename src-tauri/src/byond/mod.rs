@@ -0,0 +1,1271 @@
+mod coordinator;
+mod import;
+mod install;
+mod prefetch;
+mod prefix;
+mod releases;
+mod usage;
+mod verify;
+mod version;
+
+pub use coordinator::InstallCoordinator;
+pub use prefetch::Prefetcher;
+pub use prefix::PrefixHealth;
+#[cfg(target_os = "linux")]
+pub use prefix::{ensure_prefix, runner_command};
+pub use releases::ReleaseCache;
+pub use version::{deserialize_lenient, ByondVersion};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::auth::TokenStorage;
+use crate::connection::{ConnectionAttempt, ConnectionStateMachine};
+use crate::control_server::ControlServer;
+use crate::launcher::{dreamseeker_exe, launch, GameLauncher, LaunchRequest};
+use crate::presence::{ConnectionParams, PresenceManager};
+use crate::relays::RelayState;
+use crate::servers::{Server, ServerState};
+use crate::settings::{load_settings, AuthMode, ByondVersionOverride};
+
+#[cfg(feature = "steam")]
+use crate::steam::{authenticate_with_steam, SteamState};
+
+use import::{copy_installation, validate_archive, version_from_archive_name, ImportSource};
+use install::{
+    discard_archive, download_archive, extract_archive, new_staging_dir, promote_staging_dir,
+    CancelFlag, ProgressReporter, ARCHIVE_FILE,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByondVersionInfo {
+    pub version: ByondVersion,
+    pub installed: bool,
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledByondVersion {
+    #[serde(flatten)]
+    pub info: ByondVersionInfo,
+    pub size_bytes: u64,
+    pub installed_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Whether a server in the current list would connect with this version.
+    pub recommended: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByondDiskUsage {
+    pub versions: Vec<InstalledByondVersion>,
+    /// Everything under the BYOND base directory, including the versions,
+    /// the WebView2 data and the Wine prefix.
+    pub total_bytes: u64,
+    pub webview2_data_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneResult {
+    pub removed: Vec<ByondVersion>,
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuthError {
+    pub code: String,
+    pub message: String,
+    pub linking_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectionResult {
+    pub success: bool,
+    pub message: String,
+    pub auth_error: Option<AuthError>,
+}
+
+/// Build a BYOND connection URL with optional auth and launcher port.
+pub fn build_connect_url(
+    host: &str,
+    port: &str,
+    access_type: Option<&str>,
+    access_token: Option<&str>,
+    launcher_port: Option<&str>,
+) -> String {
+    let mut query_params = Vec::new();
+    if let (Some(access_type), Some(token)) = (access_type, access_token) {
+        query_params.push(format!("{}={}", access_type, token));
+    }
+    if let Some(port) = launcher_port {
+        query_params.push(format!("launcher_port={}", port));
+    }
+
+    if query_params.is_empty() {
+        format!("byond://{}:{}", host, port)
+    } else {
+        format!("byond://{}:{}?{}", host, port, query_params.join("&"))
+    }
+}
+
+fn get_byond_base_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    let local_data = dirs::data_local_dir()
+        .ok_or("Failed to get local data directory")?
+        .join("com.cm-ss13.launcher");
+
+    Ok(local_data.join("byond"))
+}
+
+fn get_byond_version_dir(app: &AppHandle, version: ByondVersion) -> Result<PathBuf, String> {
+    let base = get_byond_base_dir(app)?;
+    Ok(base.join(version.to_string()))
+}
+
+fn get_webview2_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_byond_base_dir(app)?.join("webview2_data"))
+}
+
+/// The Wine prefix the launcher owns and runs DreamSeeker in.
+#[cfg(target_os = "linux")]
+fn get_wine_prefix_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_byond_base_dir(app)?.join("wine_prefix"))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_wine_prefix_dir(_app: &AppHandle) -> Result<PathBuf, String> {
+    Err("Wine is only used to run BYOND on Linux".to_string())
+}
+
+/// Remove staging directories and archives left behind by installs that were
+/// interrupted, e.g. by the launcher being closed mid-extraction.
+pub fn cleanup_stale_installs(app: &AppHandle) {
+    match get_byond_base_dir(app) {
+        Ok(base_dir) => install::sweep_stale_files(&base_dir),
+        Err(e) => tracing::warn!("Skipping BYOND install cleanup: {}", e),
+    }
+}
+
+/// The managed Windows DreamSeeker build, which Linux runs through Wine.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn get_dreamseeker_path(app: &AppHandle, version: ByondVersion) -> Result<PathBuf, String> {
+    Ok(dreamseeker_exe(&get_byond_version_dir(app, version)?))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn get_dreamseeker_path(_app: &AppHandle, _version: ByondVersion) -> Result<PathBuf, String> {
+    Err("BYOND is only supported on Windows and Linux".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn get_platform_launcher(_app: &AppHandle) -> Result<Box<dyn GameLauncher>, String> {
+    Ok(Box::new(crate::launcher::NativeLauncher))
+}
+
+#[cfg(target_os = "linux")]
+fn get_platform_launcher(app: &AppHandle) -> Result<Box<dyn GameLauncher>, String> {
+    let settings = load_settings(app)?;
+    Ok(Box::new(crate::launcher::WineLauncher::new(
+        settings.wine,
+        get_wine_prefix_dir(app)?,
+    )))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn get_platform_launcher(_app: &AppHandle) -> Result<Box<dyn GameLauncher>, String> {
+    Err("BYOND is only supported on Windows and Linux".to_string())
+}
+
+/// The backend used to start DreamSeeker. Dev builds can swap in a stub
+/// process through `CM_LAUNCHER_FAKE_GAME`, which also skips installing BYOND.
+fn get_game_launcher(app: &AppHandle) -> Result<Box<dyn GameLauncher>, String> {
+    #[cfg(feature = "dev")]
+    if let Some(fake) = crate::launcher::FakeLauncher::from_env() {
+        return Ok(Box::new(fake));
+    }
+
+    get_platform_launcher(app)
+}
+
+#[tauri::command]
+pub async fn check_byond_version(
+    app: AppHandle,
+    version: ByondVersion,
+) -> Result<ByondVersionInfo, String> {
+    tracing::debug!("Checking BYOND version: {}", version);
+    let dreamseeker_path = get_dreamseeker_path(&app, version)?;
+    let installed = dreamseeker_path.exists();
+
+    Ok(ByondVersionInfo {
+        version,
+        installed,
+        path: if installed {
+            Some(dreamseeker_path.to_string_lossy().to_string())
+        } else {
+            None
+        },
+    })
+}
+
+fn get_byond_download_url(version: ByondVersion) -> String {
+    format!(
+        "https://www.byond.com/download/build/{}/{}_byond.zip",
+        version.major, version
+    )
+}
+
+#[tauri::command]
+pub async fn install_byond_version(
+    app: AppHandle,
+    version: ByondVersion,
+) -> Result<ByondVersionInfo, String> {
+    let coordinator = app
+        .try_state::<Arc<InstallCoordinator>>()
+        .ok_or("Install coordinator not available")?
+        .inner()
+        .clone();
+
+    coordinator
+        .install(version, |cancel| download_and_install(app, version, cancel))
+        .await
+}
+
+async fn download_and_install(
+    app: AppHandle,
+    version: ByondVersion,
+    cancel: CancelFlag,
+) -> Result<ByondVersionInfo, String> {
+    let existing = check_byond_version(app.clone(), version).await?;
+    if existing.installed {
+        tracing::debug!("BYOND version {} already installed", version);
+        return Ok(existing);
+    }
+
+    tracing::info!("Installing BYOND version: {}", version);
+    let download_url = get_byond_download_url(version);
+    let version_dir = get_byond_version_dir(&app, version)?;
+
+    fs::create_dir_all(&version_dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+
+    let zip_path = version_dir.join(ARCHIVE_FILE);
+
+    let mut reporter = ProgressReporter::new(app.clone(), version);
+    if let Err(e) = download_archive(&download_url, &zip_path, &mut reporter, &cancel).await {
+        // A cancelled download isn't coming back for its partial archive.
+        if cancel.is_cancelled() {
+            discard_archive(&zip_path);
+        }
+        return Err(e);
+    }
+
+    let allow_unverified = load_settings(&app)?.allow_unverified_byond;
+    let archive_hash = match verify::verify_archive(version, &zip_path, allow_unverified).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            discard_archive(&zip_path);
+            return Err(e);
+        }
+    };
+
+    // Extract somewhere `check_byond_version` won't look, so a failure part-way
+    // through can never leave a half-populated install that looks complete.
+    let staging_dir = new_staging_dir(&get_byond_base_dir(&app)?, version);
+    fs::create_dir_all(&staging_dir)
+        .map_err(|e| format!("Failed to create staging directory: {}", e))?;
+
+    let extract_zip_path = zip_path.clone();
+    let extract_dir = staging_dir.clone();
+    let extract_cancel = cancel.clone();
+    let extracted = tokio::task::spawn_blocking(move || {
+        extract_archive(
+            &extract_zip_path,
+            &extract_dir,
+            &mut reporter,
+            &extract_cancel,
+        )
+    })
+    .await
+    .map_err(|e| format!("Extraction task failed: {}", e))
+    .and_then(|result| result)
+    .and_then(|()| match &archive_hash {
+        Some(hash) => verify::record_hash(&staging_dir, hash),
+        None => Ok(()),
+    })
+    .and_then(|()| usage::record_install(&staging_dir));
+
+    discard_archive(&zip_path);
+
+    if let Err(e) = extracted
+        .and_then(|()| cancel.check())
+        .and_then(|()| promote_staging_dir(&staging_dir, &version_dir))
+    {
+        fs::remove_dir_all(&staging_dir).ok();
+        return Err(e);
+    }
+
+    tracing::info!("BYOND version {} installed successfully", version);
+
+    if load_settings(&app)?.byond_retention.auto_prune {
+        if let Err(e) = prune_unused_versions(&app, Some(version)).await {
+            tracing::warn!("Automatic BYOND prune failed: {}", e);
+        }
+    }
+
+    check_byond_version(app, version).await
+}
+
+/// Register a local `{version}_byond.zip` or an existing BYOND directory as a
+/// managed install, for players who can't download from byond.com. The
+/// version is read from the archive name when not given.
+#[tauri::command]
+pub async fn import_byond_version(
+    app: AppHandle,
+    path: String,
+    version: Option<ByondVersion>,
+) -> Result<ByondVersionInfo, String> {
+    let source = ImportSource::from_path(PathBuf::from(path))?;
+
+    let version = match (&source, version) {
+        (_, Some(version)) => version,
+        (ImportSource::Archive(zip_path), None) => version_from_archive_name(zip_path)
+            .ok_or("Could not tell the BYOND version from the archive name")?,
+        (ImportSource::Directory(_), None) => {
+            return Err("A BYOND version is required to import a directory".to_string())
+        }
+    };
+
+    if check_byond_version(app.clone(), version).await?.installed {
+        return Err(format!("BYOND {} is already installed", version));
+    }
+
+    let coordinator = app
+        .try_state::<Arc<InstallCoordinator>>()
+        .ok_or("Install coordinator not available")?
+        .inner()
+        .clone();
+
+    coordinator
+        .install(version, |cancel| {
+            import_and_install(app, version, source, cancel)
+        })
+        .await
+}
+
+async fn import_and_install(
+    app: AppHandle,
+    version: ByondVersion,
+    source: ImportSource,
+    cancel: CancelFlag,
+) -> Result<ByondVersionInfo, String> {
+    tracing::info!("Importing BYOND version: {}", version);
+
+    // The player picked the source themselves, so a version with no known
+    // hash is allowed; a known one must still match. Only a matched hash is
+    // recorded, so an unverified import never looks like a checked download.
+    let archive_hash = match &source {
+        ImportSource::Archive(zip_path) => {
+            validate_archive(zip_path)?;
+            verify::verify_archive(version, zip_path, true).await?
+        }
+        ImportSource::Directory(dir) => {
+            tracing::warn!(
+                "Importing BYOND {} from {} unverified: the version is as given and no archive hash is checked",
+                version,
+                dir.display()
+            );
+            None
+        }
+    };
+
+    let base_dir = get_byond_base_dir(&app)?;
+    let version_dir = get_byond_version_dir(&app, version)?;
+    let staging_dir = new_staging_dir(&base_dir, version);
+    fs::create_dir_all(&staging_dir)
+        .map_err(|e| format!("Failed to create staging directory: {}", e))?;
+
+    let mut reporter = ProgressReporter::new(app.clone(), version);
+    let import_dir = staging_dir.clone();
+    let import_cancel = cancel.clone();
+    let imported = tokio::task::spawn_blocking(move || match source {
+        ImportSource::Archive(zip_path) => {
+            extract_archive(&zip_path, &import_dir, &mut reporter, &import_cancel)
+        }
+        ImportSource::Directory(dir) => copy_installation(&dir, &import_dir, &import_cancel),
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))
+    .and_then(|result| result)
+    .and_then(|()| match &archive_hash {
+        Some(hash) => verify::record_hash(&staging_dir, hash),
+        None => Ok(()),
+    })
+    .and_then(|()| usage::record_install(&staging_dir))
+    .and_then(|()| cancel.check())
+    .and_then(|()| promote_staging_dir(&staging_dir, &version_dir));
+
+    if let Err(e) = imported {
+        fs::remove_dir_all(&staging_dir).ok();
+        return Err(e);
+    }
+
+    tracing::info!("BYOND version {} imported successfully", version);
+
+    check_byond_version(app, version).await
+}
+
+/// Start installing the versions `servers` would connect with in the
+/// background, if the player has turned prefetching on. Servers that accept a
+/// range need nothing when an installed version already fits it.
+pub async fn prefetch_server_versions(app: &AppHandle, servers: &[Server]) {
+    match load_settings(app) {
+        Ok(settings) if settings.prefetch_byond_versions => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!("Skipping BYOND prefetch: {}", e);
+            return;
+        }
+    }
+
+    let Some(prefetcher) = app.try_state::<Arc<Prefetcher>>() else {
+        return;
+    };
+
+    let versions = resolved_server_versions(app, servers).await;
+    prefetcher.start(app.clone(), versions.into_iter().collect());
+}
+
+#[tauri::command]
+pub async fn cancel_byond_prefetch(app: AppHandle) -> Result<bool, String> {
+    let prefetcher = app
+        .try_state::<Arc<Prefetcher>>()
+        .ok_or("Prefetcher not available")?;
+    Ok(prefetcher.cancel())
+}
+
+/// The version to connect to `server` with: the one it recommends, unless
+/// the player has set an override for it. Servers that accept a range of
+/// versions get the newest installed version inside it, so a download is
+/// only needed when nothing installed fits. See `Server::version_in_range`.
+pub async fn resolve_server_version(
+    app: &AppHandle,
+    server: &Server,
+) -> Result<ByondVersion, String> {
+    let recommended = server.recommended_byond_version;
+    let settings = load_settings(app)?;
+
+    let overridden = match settings.byond_version_overrides.get(&server.name) {
+        Some(ByondVersionOverride::Pinned(version)) => Some(*version),
+        Some(ByondVersionOverride::NewestInMajor) => match recommended {
+            Some(recommended) => installed_versions(app)
+                .await?
+                .into_iter()
+                .map(|info| info.version)
+                .filter(|version| version.major == recommended.major)
+                .max(),
+            None => None,
+        },
+        None => None,
+    };
+
+    match (overridden, recommended) {
+        (Some(version), _) => {
+            tracing::info!(
+                "Using BYOND {} for {} per version override (server recommends {})",
+                version,
+                server.name,
+                recommended.map_or_else(|| "none".to_string(), |v| v.to_string())
+            );
+            Ok(version)
+        }
+        (None, _) if server.has_version_range() => {
+            let installed: Vec<ByondVersion> = installed_versions(app)
+                .await?
+                .into_iter()
+                .map(|info| info.version)
+                .collect();
+
+            match server.version_in_range(&installed) {
+                Some(version) => {
+                    tracing::debug!(
+                        "Using BYOND {} for {} from its version range",
+                        version,
+                        server.name
+                    );
+                    Ok(version)
+                }
+                None => Err("Server has no BYOND version in its accepted range".to_string()),
+            }
+        }
+        (None, Some(recommended)) => Ok(recommended),
+        (None, None) => Err("Server has no recommended BYOND version".to_string()),
+    }
+}
+
+/// Start tracking a connection attempt, or return `None` when another is
+/// already in progress.
+pub fn begin_connection(
+    app: &AppHandle,
+    server_name: &str,
+    source: Option<&str>,
+) -> Result<Option<ConnectionAttempt>, String> {
+    let machine = app
+        .try_state::<Arc<ConnectionStateMachine>>()
+        .ok_or("Connection state not available")?;
+
+    let attempt = machine.begin(app, server_name);
+    if attempt.is_none() {
+        tracing::warn!(
+            "[connect_to_server] BLOCKED duplicate connection attempt, source={} server={}",
+            source.unwrap_or("unknown"),
+            server_name
+        );
+    }
+    Ok(attempt)
+}
+
+fn connection_in_progress() -> ConnectionResult {
+    ConnectionResult {
+        success: false,
+        message: "Connection already in progress".to_string(),
+        auth_error: None,
+    }
+}
+
+fn connection_cancelled(attempt: ConnectionAttempt) -> ConnectionResult {
+    tracing::info!(
+        "[connect_to_server] cancelled server={}",
+        attempt.server_name()
+    );
+    attempt.cancelled();
+    ConnectionResult {
+        success: false,
+        message: "Connection cancelled".to_string(),
+        auth_error: None,
+    }
+}
+
+/// Internal function for connecting with explicit auth params.
+/// Used by autoconnect and the simplified connect_to_server command.
+pub async fn connect_to_server_internal(
+    app: AppHandle,
+    version: ByondVersion,
+    host: String,
+    port: String,
+    access_type: Option<String>,
+    access_token: Option<String>,
+    server_name: String,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    let Some(attempt) = begin_connection(&app, &server_name, source.as_deref())? else {
+        return Ok(connection_in_progress());
+    };
+
+    connect_with_attempt(
+        attempt,
+        version,
+        host,
+        port,
+        access_type,
+        access_token,
+        source,
+    )
+    .await
+}
+
+pub async fn connect_with_attempt(
+    attempt: ConnectionAttempt,
+    version: ByondVersion,
+    host: String,
+    port: String,
+    access_type: Option<String>,
+    access_token: Option<String>,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    tracing::info!(
+        "[connect_to_server] source={} server={} version={}",
+        source.as_deref().unwrap_or("unknown"),
+        attempt.server_name(),
+        version
+    );
+
+    let result = attempt
+        .run(connect_to_server_impl(
+            &attempt,
+            version,
+            host,
+            port,
+            access_type,
+            access_token,
+            source,
+        ))
+        .await;
+
+    let Ok(result) = result else {
+        return Ok(connection_cancelled(attempt));
+    };
+
+    match &result {
+        Ok(_) => attempt.launched(),
+        Err(e) => attempt.fail(e),
+    }
+    result
+}
+
+/// Authenticate for an attempt, recording a failure in the connection state.
+/// `auth_mode` overrides the player's saved mode, e.g. for a custom server.
+async fn authenticate_attempt(
+    attempt: ConnectionAttempt,
+    auth_mode: Option<AuthMode>,
+) -> Result<(ConnectionAttempt, Option<String>, Option<String>), ConnectionResult> {
+    let Ok(auth) = attempt
+        .run(get_auth_for_connection(attempt.app(), auth_mode))
+        .await
+    else {
+        return Err(connection_cancelled(attempt));
+    };
+
+    match auth {
+        Ok((access_type, access_token)) => Ok((attempt, access_type, access_token)),
+        Err(auth_error) => {
+            attempt.fail(&auth_error.message);
+            Err(ConnectionResult {
+                success: false,
+                message: auth_error.message.clone(),
+                auth_error: Some(auth_error),
+            })
+        }
+    }
+}
+
+async fn get_auth_for_connection(
+    app: &AppHandle,
+    auth_mode: Option<AuthMode>,
+) -> Result<(Option<String>, Option<String>), AuthError> {
+    let auth_mode = match auth_mode {
+        Some(auth_mode) => auth_mode,
+        None => {
+            load_settings(app)
+                .map_err(|e| AuthError {
+                    code: "settings_error".to_string(),
+                    message: e,
+                    linking_url: None,
+                })?
+                .auth_mode
+        }
+    };
+
+    get_auth_for_mode(app, auth_mode).await
+}
+
+pub(crate) async fn get_auth_for_mode(
+    #[allow(unused_variables)] app: &AppHandle,
+    auth_mode: AuthMode,
+) -> Result<(Option<String>, Option<String>), AuthError> {
+    match auth_mode {
+        AuthMode::CmSs13 => {
+            let tokens = TokenStorage::get_tokens().map_err(|e| AuthError {
+                code: "token_error".to_string(),
+                message: e,
+                linking_url: None,
+            })?;
+
+            match tokens {
+                Some(t) if !TokenStorage::is_expired() => {
+                    Ok((Some("cm_ss13".to_string()), Some(t.access_token)))
+                }
+                _ => Err(AuthError {
+                    code: "auth_required".to_string(),
+                    message: "Please log in to CM-SS13".to_string(),
+                    linking_url: None,
+                }),
+            }
+        }
+        AuthMode::Steam => {
+            #[cfg(feature = "steam")]
+            {
+                let steam_state = app
+                    .try_state::<Arc<SteamState>>()
+                    .ok_or_else(|| AuthError {
+                        code: "steam_unavailable".to_string(),
+                        message: "Steam is not available".to_string(),
+                        linking_url: None,
+                    })?;
+
+                let result = authenticate_with_steam(&steam_state, false)
+                    .await
+                    .map_err(|e| AuthError {
+                        code: "steam_error".to_string(),
+                        message: e,
+                        linking_url: None,
+                    })?;
+
+                if result.success {
+                    Ok((Some("steam".to_string()), result.access_token))
+                } else if result.requires_linking {
+                    Err(AuthError {
+                        code: "steam_linking_required".to_string(),
+                        message: "Steam account linking required".to_string(),
+                        linking_url: result.linking_url,
+                    })
+                } else {
+                    Err(AuthError {
+                        code: "steam_auth_failed".to_string(),
+                        message: result
+                            .error
+                            .unwrap_or_else(|| "Steam authentication failed".to_string()),
+                        linking_url: None,
+                    })
+                }
+            }
+
+            #[cfg(not(feature = "steam"))]
+            {
+                Err(AuthError {
+                    code: "steam_unavailable".to_string(),
+                    message: "Steam support not compiled".to_string(),
+                    linking_url: None,
+                })
+            }
+        }
+        AuthMode::Byond => {
+            if !check_byond_pager_running() {
+                return Err(AuthError {
+                    code: "byond_pager_not_running".to_string(),
+                    message: "BYOND pager is not running. Please open BYOND and log in before connecting.".to_string(),
+                    linking_url: None,
+                });
+            }
+            Ok((Some("byond".to_string()), None))
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn connect_to_server(
+    app: AppHandle,
+    server_name: String,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    let source_str = source.as_deref().unwrap_or("unknown");
+
+    let server_state = app
+        .try_state::<Arc<ServerState>>()
+        .ok_or("Server state not available")?;
+    let servers = server_state.get_servers().await;
+    let server = servers
+        .iter()
+        .find(|s| s.name == server_name)
+        .ok_or_else(|| format!("Server '{}' not found", server_name))?
+        .clone();
+
+    let version = resolve_server_version(&app, &server).await?;
+
+    let port = server
+        .url
+        .split(':')
+        .nth(1)
+        .ok_or("Invalid server URL format")?
+        .to_string();
+
+    let relay_state = app
+        .try_state::<Arc<RelayState>>()
+        .ok_or("Relay state not available")?;
+    let host = relay_state
+        .get_selected_host()
+        .await
+        .ok_or("No relay selected")?;
+
+    let Some(attempt) = begin_connection(&app, &server_name, source.as_deref())? else {
+        return Ok(connection_in_progress());
+    };
+
+    let (attempt, access_type, access_token) = match authenticate_attempt(attempt, None).await {
+        Ok(auth) => auth,
+        Err(result) => return Ok(result),
+    };
+
+    tracing::info!(
+        "[connect_to_server] source={} server={} version={} host={}",
+        source_str,
+        server_name,
+        version,
+        host
+    );
+
+    connect_with_attempt(
+        attempt,
+        version,
+        host,
+        port,
+        access_type,
+        access_token,
+        source,
+    )
+    .await
+}
+
+async fn connect_to_server_impl(
+    attempt: &ConnectionAttempt,
+    version: ByondVersion,
+    host: String,
+    port: String,
+    access_type: Option<String>,
+    access_token: Option<String>,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    let app = attempt.app().clone();
+    let server_name = attempt.server_name().to_string();
+
+    let launcher = get_game_launcher(&app)?;
+
+    if launcher.needs_install() {
+        attempt.installing(version);
+        let version_info = install_byond_version(app.clone(), version).await?;
+
+        if !version_info.installed {
+            let msg = format!("Failed to install BYOND version {}", version);
+            tracing::error!("{}", msg);
+            return Err(msg);
+        }
+    }
+
+    attempt.launching(version);
+
+    let version_dir = get_byond_version_dir(&app, version)?;
+    let executable = launcher.resolve_executable(&version_dir);
+
+    if let Some(control_server) = app.try_state::<ControlServer>() {
+        control_server.reset_connected_flag();
+    }
+
+    if source.as_deref() != Some("control_server_restart") {
+        app.emit("game-connecting", &server_name).ok();
+    }
+
+    let control_port = app.try_state::<ControlServer>().map(|s| s.port.to_string());
+
+    let connect_url = build_connect_url(
+        &host,
+        &port,
+        access_type.as_deref(),
+        access_token.as_deref(),
+        control_port.as_deref(),
+    );
+
+    // Set a unique WebView2 user data folder to avoid conflicts with the system BYOND pager.
+    // When the BYOND pager is running, it locks the default WebView2 user data directory,
+    // preventing our DreamSeeker from using WebView2. Using a separate folder resolves this.
+    let webview2_data_dir = get_webview2_data_dir(&app)?;
+
+    let request = LaunchRequest {
+        executable,
+        connect_url,
+        webview2_data_dir,
+    };
+
+    let child = launch(launcher.as_ref(), &request).await?;
+    if launcher.needs_install() {
+        usage::record_launch(&version_dir);
+    }
+
+    if let Some(manager) = app.try_state::<Arc<PresenceManager>>() {
+        manager.set_last_connection_params(ConnectionParams {
+            version,
+            host: host.clone(),
+            port: port.clone(),
+            access_type,
+            access_token,
+            server_name: server_name.clone(),
+        });
+
+        manager.start_game_session(
+            server_name,
+            "https://db.cm-ss13.com/api/Round".to_string(),
+            child,
+        );
+    }
+
+    Ok(ConnectionResult {
+        success: true,
+        message: format!("Connecting to {} with BYOND {}", host, version),
+        auth_error: None,
+    })
+}
+
+#[tauri::command]
+pub async fn get_wine_prefix_status(app: AppHandle) -> Result<PrefixHealth, String> {
+    let prefix_dir = get_wine_prefix_dir(&app)?;
+    let settings = load_settings(&app)?;
+    Ok(prefix::load_health(&prefix_dir, settings.wine.runner))
+}
+
+#[tauri::command]
+pub async fn rebuild_wine_prefix(app: AppHandle) -> Result<PrefixHealth, String> {
+    let prefix_dir = get_wine_prefix_dir(&app)?;
+    let settings = load_settings(&app)?;
+
+    tokio::task::spawn_blocking(move || prefix::rebuild_prefix(&settings.wine, &prefix_dir))
+        .await
+        .map_err(|e| format!("Wine prefix task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn delete_wine_prefix(app: AppHandle) -> Result<bool, String> {
+    let prefix_dir = get_wine_prefix_dir(&app)?;
+    prefix::delete_prefix(&prefix_dir)
+}
+
+async fn installed_versions(app: &AppHandle) -> Result<Vec<ByondVersionInfo>, String> {
+    let base_dir = get_byond_base_dir(app)?;
+
+    if !base_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut versions = Vec::new();
+
+    let entries =
+        fs::read_dir(&base_dir).map_err(|e| format!("Failed to read BYOND directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read directory entry: {}", e))?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            // Skip anything that isn't a version, like the install staging directory.
+            if let Some(version) = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.parse::<ByondVersion>().ok())
+            {
+                let info = check_byond_version(app.clone(), version).await?;
+                if info.installed {
+                    versions.push(info);
+                }
+            }
+        }
+    }
+
+    Ok(versions)
+}
+
+#[tauri::command]
+pub async fn list_installed_byond_versions(app: AppHandle) -> Result<ByondDiskUsage, String> {
+    let base_dir = get_byond_base_dir(&app)?;
+    let webview2_data_dir = get_webview2_data_dir(&app)?;
+    let installed = installed_versions(&app).await?;
+    let recommended = server_versions(&app).await;
+
+    // Walking the install directories can take a moment on slow disks.
+    tokio::task::spawn_blocking(move || {
+        let versions = installed
+            .into_iter()
+            .map(|info| {
+                let version_dir = base_dir.join(info.version.to_string());
+                InstalledByondVersion {
+                    size_bytes: usage::dir_size(&version_dir),
+                    installed_at: usage::installed_at(&version_dir),
+                    last_used_at: usage::last_launched(&version_dir),
+                    recommended: recommended.contains(&info.version),
+                    info,
+                }
+            })
+            .collect();
+
+        ByondDiskUsage {
+            versions,
+            total_bytes: usage::dir_size(&base_dir),
+            webview2_data_bytes: usage::dir_size(&webview2_data_dir),
+        }
+    })
+    .await
+    .map_err(|e| format!("Disk usage task failed: {}", e))
+}
+
+/// The version each of `servers` would connect with, as chosen by
+/// `resolve_server_version`.
+async fn resolved_server_versions(app: &AppHandle, servers: &[Server]) -> HashSet<ByondVersion> {
+    let mut versions = HashSet::new();
+    for server in servers {
+        match resolve_server_version(app, server).await {
+            Ok(version) => {
+                versions.insert(version);
+            }
+            Err(e) => tracing::debug!("No BYOND version for {}: {}", server.name, e),
+        }
+    }
+    versions
+}
+
+/// The version each server in the current list would connect with.
+async fn server_versions(app: &AppHandle) -> HashSet<ByondVersion> {
+    match app.try_state::<Arc<ServerState>>() {
+        Some(server_state) => {
+            let servers = server_state.get_servers().await;
+            resolved_server_versions(app, &servers).await
+        }
+        None => HashSet::new(),
+    }
+}
+
+/// Versions that must survive a prune regardless of the retention policy: any
+/// a listed server would connect with, any the player pinned or set for a
+/// custom server, and the one the running game is using.
+async fn protected_versions(app: &AppHandle) -> HashSet<ByondVersion> {
+    let mut protected = server_versions(app).await;
+
+    match load_settings(app) {
+        Ok(settings) => {
+            protected.extend(settings.byond_version_overrides.values().filter_map(
+                |version_override| match version_override {
+                    ByondVersionOverride::Pinned(version) => Some(*version),
+                    ByondVersionOverride::NewestInMajor => None,
+                },
+            ));
+            protected.extend(settings.custom_servers.iter().map(|s| s.byond_version));
+        }
+        Err(e) => tracing::warn!("Failed to load settings for pruning: {}", e),
+    }
+
+    if let Some(manager) = app.try_state::<Arc<PresenceManager>>() {
+        if manager.check_game_running() {
+            if let Some(params) = manager.get_last_connection_params() {
+                protected.insert(params.version);
+            }
+        }
+    }
+
+    protected
+}
+
+/// Delete installed versions the retention policy doesn't keep. `keep` is
+/// always kept, so a version that was just installed isn't removed before
+/// it's ever launched.
+async fn prune_unused_versions(
+    app: &AppHandle,
+    keep: Option<ByondVersion>,
+) -> Result<PruneResult, String> {
+    let retention = load_settings(app)?.byond_retention;
+    let base_dir = get_byond_base_dir(app)?;
+
+    let mut protected = protected_versions(app).await;
+    protected.extend(keep);
+
+    let installed = installed_versions(app)
+        .await?
+        .into_iter()
+        .map(|info| {
+            let launched = usage::last_launched(&base_dir.join(info.version.to_string()));
+            (info.version, launched)
+        })
+        .collect();
+
+    // Always keep at least one version, so the last one played isn't removed.
+    let keep_recent = retention.keep_recent.max(1);
+
+    let mut result = PruneResult {
+        removed: Vec::new(),
+        freed_bytes: 0,
+    };
+
+    for version in usage::select_for_pruning(installed, keep_recent, &protected) {
+        let version_dir = base_dir.join(version.to_string());
+        let size = usage::dir_size(&version_dir);
+
+        match delete_byond_version(app.clone(), version).await {
+            Ok(_) => {
+                result.removed.push(version);
+                result.freed_bytes += size;
+            }
+            Err(e) => tracing::warn!("Failed to prune BYOND {}: {}", version, e),
+        }
+    }
+
+    tracing::info!(
+        "Pruned {} BYOND version(s), freed {} bytes",
+        result.removed.len(),
+        result.freed_bytes
+    );
+
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn prune_byond_versions(app: AppHandle) -> Result<PruneResult, String> {
+    prune_unused_versions(&app, None).await
+}
+
+/// The latest stable and beta builds, and which installed majors have a newer
+/// build available.
+#[tauri::command]
+pub async fn get_latest_byond_versions(
+    app: AppHandle,
+    refresh: Option<bool>,
+) -> Result<releases::LatestByondVersions, String> {
+    let cache = app
+        .try_state::<Arc<ReleaseCache>>()
+        .ok_or("Release cache not available")?;
+
+    let (channels, fetched_at) = cache.channels(refresh.unwrap_or(false)).await?;
+    let installed: Vec<ByondVersion> = installed_versions(&app)
+        .await?
+        .into_iter()
+        .map(|info| info.version)
+        .collect();
+
+    Ok(releases::LatestByondVersions {
+        channels,
+        fetched_at,
+        majors: releases::find_updates(&channels, &installed),
+    })
+}
+
+#[tauri::command]
+pub async fn delete_byond_version(app: AppHandle, version: ByondVersion) -> Result<bool, String> {
+    let version_dir = get_byond_version_dir(&app, version)?;
+
+    if version_dir.exists() {
+        tracing::info!("Deleting BYOND version: {}", version);
+        fs::remove_dir_all(&version_dir)
+            .map_err(|e| format!("Failed to delete BYOND version: {}", e))?;
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
+fn check_byond_pager_running() -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        use sysinfo::System;
+
+        let s = System::new_all();
+        s.processes().values().any(|p| {
+            p.name()
+                .to_str()
+                .map(|name| name.eq_ignore_ascii_case("byond.exe"))
+                .unwrap_or(false)
+        })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        false
+    }
+}
+
+/// Connect to one of the player's custom servers. These skip the relays and
+/// the official server list, and may use their own auth mode.
+#[tauri::command]
+pub async fn connect_to_custom_server(
+    app: AppHandle,
+    name: String,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    let settings = load_settings(&app)?;
+    let server = settings
+        .custom_servers
+        .into_iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| format!("Custom server '{}' not found", name))?;
+    let (host, port) = server.host_and_port()?;
+
+    let Some(attempt) = begin_connection(&app, &server.name, source.as_deref())? else {
+        return Ok(connection_in_progress());
+    };
+
+    // Only servers the player opted in for get a login token
+    let (attempt, access_type, access_token) = match server.auth_mode {
+        Some(auth_mode) => match authenticate_attempt(attempt, Some(auth_mode)).await {
+            Ok(auth) => auth,
+            Err(result) => return Ok(result),
+        },
+        None => (attempt, None, None),
+    };
+
+    tracing::info!(
+        "[connect_to_custom_server] server={} address={}:{} version={}",
+        server.name,
+        host,
+        port,
+        server.byond_version
+    );
+
+    connect_with_attempt(
+        attempt,
+        server.byond_version,
+        host,
+        port,
+        access_type,
+        access_token,
+        source,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn is_byond_pager_running() -> Result<bool, String> {
+    Ok(check_byond_pager_running())
+}
+#[tauri::command]
+pub fn is_dev_mode() -> bool {
+    cfg!(feature = "dev")
+}
+
+#[tauri::command]
+pub async fn connect_to_url(
+    app: AppHandle,
+    url: String,
+    version: ByondVersion,
+    source: Option<String>,
+) -> Result<ConnectionResult, String> {
+    #[cfg(not(feature = "dev"))]
+    {
+        let _ = (app, url, version, source);
+        return Err("Dev mode is not enabled".to_string());
+    }
+
+    #[cfg(feature = "dev")]
+    {
+        let url = url.strip_prefix("byond://").unwrap_or(&url).to_string();
+
+        let parts: Vec<&str> = url.split(':').collect();
+        if parts.len() != 2 {
+            return Err("Invalid URL format. Expected 'host:port'".to_string());
+        }
+
+        let host = parts[0].to_string();
+        let port = parts[1].to_string();
+
+        let server_name = format!("Dev Server ({})", url);
+        let Some(attempt) = begin_connection(&app, &server_name, source.as_deref())? else {
+            return Ok(connection_in_progress());
+        };
+
+        let (attempt, access_type, access_token) = match authenticate_attempt(attempt, None).await {
+            Ok(auth) => auth,
+            Err(result) => return Ok(result),
+        };
+
+        tracing::info!(
+            "[connect_to_url] dev mode connection to {}:{} version={}",
+            host,
+            port,
+            version
+        );
+
+        connect_with_attempt(
+            attempt,
+            version,
+            host,
+            port,
+            access_type,
+            access_token,
+            source,
+        )
+        .await
+    }
+}
@@ -0,0 +1,199 @@
+//! Background installs of the BYOND versions servers recommend
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+use super::{check_byond_version, install_byond_version, ByondVersion};
+use crate::presence::PresenceManager;
+
+/// Pause between prefetched installs, so a long queue doesn't saturate the
+/// connection for minutes on end. This also applies across runs, as a new
+/// run starts after every server list refresh.
+const PREFETCH_SPACING: Duration = Duration::from_secs(30);
+/// How often to check whether the game has closed before prefetching more.
+const GAME_RUNNING_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What prefetching remembers between runs.
+struct PrefetchState {
+    /// Versions that failed to install, skipped for the rest of the session
+    /// rather than downloaded again on every run.
+    failed: HashSet<ByondVersion>,
+    /// When the last install ended, or when the launcher started.
+    last_install: Instant,
+}
+
+impl PrefetchState {
+    fn new(now: Instant) -> Self {
+        Self {
+            failed: HashSet::new(),
+            last_install: now,
+        }
+    }
+
+    /// How long to wait before the next install may start.
+    fn delay(&self, now: Instant) -> Duration {
+        (self.last_install + PREFETCH_SPACING).saturating_duration_since(now)
+    }
+
+    fn finished(&mut self, version: ByondVersion, succeeded: bool, now: Instant) {
+        self.last_install = now;
+        if !succeeded {
+            self.failed.insert(version);
+        }
+    }
+}
+
+/// Owns the background prefetch task. Only one runs at a time.
+pub struct Prefetcher {
+    task: Mutex<Option<JoinHandle<()>>>,
+    state: Arc<Mutex<PrefetchState>>,
+}
+
+impl Prefetcher {
+    pub fn new() -> Self {
+        Self {
+            task: Mutex::new(None),
+            state: Arc::new(Mutex::new(PrefetchState::new(Instant::now()))),
+        }
+    }
+
+    /// Queue installs for whichever of `versions` aren't on disk yet. Does
+    /// nothing while an earlier prefetch is still working through its queue.
+    pub fn start(&self, app: AppHandle, versions: Vec<ByondVersion>) {
+        let mut task = self.task.lock().unwrap();
+        if task.as_ref().is_some_and(|t| !t.inner().is_finished()) {
+            return;
+        }
+
+        let state = Arc::clone(&self.state);
+        *task = Some(tauri::async_runtime::spawn(prefetch_versions(
+            app, state, versions,
+        )));
+    }
+
+    /// Stop the running prefetch. An install it started is cancelled too,
+    /// unless a connection is also waiting on it.
+    pub fn cancel(&self) -> bool {
+        match self.task.lock().unwrap().take() {
+            Some(task) if !task.inner().is_finished() => {
+                tracing::info!("Cancelling BYOND prefetch");
+                task.abort();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for Prefetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wait until DreamSeeker isn't running, so prefetching never competes with
+/// the game for bandwidth.
+async fn wait_for_game_to_close(app: &AppHandle) {
+    let Some(manager) = app.try_state::<Arc<PresenceManager>>() else {
+        return;
+    };
+
+    while manager.check_game_running() {
+        tokio::time::sleep(GAME_RUNNING_POLL_INTERVAL).await;
+    }
+}
+
+async fn prefetch_versions(
+    app: AppHandle,
+    state: Arc<Mutex<PrefetchState>>,
+    mut versions: Vec<ByondVersion>,
+) {
+    versions.sort();
+    versions.dedup();
+
+    for version in versions {
+        if state.lock().unwrap().failed.contains(&version) {
+            tracing::debug!("Skipping prefetch of BYOND {}: failed earlier", version);
+            continue;
+        }
+
+        match check_byond_version(app.clone(), version).await {
+            Ok(info) if info.installed => continue,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!("Skipping prefetch of BYOND {}: {}", version, e);
+                continue;
+            }
+        }
+
+        let delay = state.lock().unwrap().delay(Instant::now());
+        tokio::time::sleep(delay).await;
+
+        wait_for_game_to_close(&app).await;
+
+        tracing::info!("Prefetching BYOND {}", version);
+        let result = install_byond_version(app.clone(), version).await;
+        match &result {
+            Ok(_) => tracing::info!("Prefetched BYOND {}", version),
+            Err(e) => tracing::warn!(
+                "Failed to prefetch BYOND {}, not retrying this session: {}",
+                version,
+                e
+            ),
+        }
+
+        state
+            .lock()
+            .unwrap()
+            .finished(version, result.is_ok(), Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(build: u32) -> ByondVersion {
+        ByondVersion { major: 516, build }
+    }
+
+    #[test]
+    fn waits_after_startup() {
+        let start = Instant::now();
+        let state = PrefetchState::new(start);
+
+        assert_eq!(state.delay(start), PREFETCH_SPACING);
+        assert_eq!(state.delay(start + PREFETCH_SPACING), Duration::ZERO);
+    }
+
+    #[test]
+    fn spaces_installs_from_the_last_one() {
+        let start = Instant::now();
+        let mut state = PrefetchState::new(start);
+
+        let finished = start + Duration::from_secs(120);
+        state.finished(version(1667), true, finished);
+
+        assert_eq!(state.delay(finished), PREFETCH_SPACING);
+        assert_eq!(
+            state.delay(finished + Duration::from_secs(10)),
+            PREFETCH_SPACING - Duration::from_secs(10)
+        );
+        assert_eq!(state.delay(finished + 2 * PREFETCH_SPACING), Duration::ZERO);
+    }
+
+    #[test]
+    fn remembers_failed_versions() {
+        let start = Instant::now();
+        let mut state = PrefetchState::new(start);
+
+        state.finished(version(1667), false, start);
+        state.finished(version(1668), true, start);
+
+        assert!(state.failed.contains(&version(1667)));
+        assert!(!state.failed.contains(&version(1668)));
+    }
+}
@@ -0,0 +1,284 @@
+//! The launcher-owned Wine prefix DreamSeeker runs in on Linux
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::settings::{WineRunner, WineSettings};
+
+const HEALTH_FILE: &str = "launcher_prefix.json";
+const WEBVIEW2_INSTALLER_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
+const WEBVIEW2_INSTALLER_FILE: &str = "MicrosoftEdgeWebview2Setup.exe";
+
+/// DLL overrides written to the prefix registry. `winemenubuilder` is turned
+/// off so the prefix can't add menu entries or file associations to the host.
+const DLL_OVERRIDES: &[(&str, &str)] = &[("winemenubuilder.exe", "")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefixStatus {
+    /// No prefix has been created yet.
+    Missing,
+    /// Every setup step succeeded.
+    Ready,
+    /// The prefix boots, but an optional step such as fonts failed.
+    Degraded,
+    /// `wineboot` failed, so nothing can run in the prefix.
+    Broken,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefixHealth {
+    pub status: PrefixStatus,
+    pub path: String,
+    pub runner: WineRunner,
+    pub initialized: bool,
+    pub overrides_applied: bool,
+    pub webview2_installed: bool,
+    pub fonts_installed: bool,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+impl PrefixHealth {
+    fn missing(prefix_dir: &Path, runner: WineRunner) -> Self {
+        Self {
+            status: PrefixStatus::Missing,
+            path: prefix_dir.to_string_lossy().to_string(),
+            runner,
+            initialized: false,
+            overrides_applied: false,
+            webview2_installed: false,
+            fonts_installed: false,
+            updated_at: None,
+            error: None,
+        }
+    }
+
+    fn record(&mut self, result: Result<(), String>) -> bool {
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Wine prefix setup step failed: {}", e);
+                self.error = Some(e);
+                false
+            }
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        self.status = if !self.initialized {
+            PrefixStatus::Broken
+        } else if self.overrides_applied && self.webview2_installed && self.fonts_installed {
+            PrefixStatus::Ready
+        } else {
+            PrefixStatus::Degraded
+        };
+        self.updated_at = Some(Utc::now());
+        self
+    }
+}
+
+/// A command that runs a Windows program inside the prefix. Callers add the
+/// program and its arguments.
+pub fn runner_command(settings: &WineSettings, prefix_dir: &Path) -> Result<Command, String> {
+    let mut command = match settings.runner {
+        WineRunner::Wine => {
+            let mut command = Command::new(settings.binary.as_deref().unwrap_or("wine"));
+            command.env("WINEPREFIX", prefix_dir);
+            command
+        }
+        WineRunner::Proton => {
+            let proton = settings
+                .binary
+                .as_deref()
+                .ok_or("Set the path to Proton's `proton` script to launch with Proton")?;
+
+            let mut command = Command::new(proton);
+            command.arg("run").env("STEAM_COMPAT_DATA_PATH", prefix_dir);
+
+            if let Some(steam_dir) = dirs::home_dir().map(|home| home.join(".steam").join("steam"))
+            {
+                command.env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_dir);
+            }
+
+            command
+        }
+    };
+
+    if std::env::var_os("WINEDEBUG").is_none() {
+        command.env("WINEDEBUG", "-all");
+    }
+
+    Ok(command)
+}
+
+fn run_in_prefix(settings: &WineSettings, prefix_dir: &Path, args: &[&str]) -> Result<(), String> {
+    let status = runner_command(settings, prefix_dir)?
+        .args(args)
+        // Skip Wine's Mono install prompt; nothing we run needs .NET.
+        .env("WINEDLLOVERRIDES", "mscoree=")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to run `{}`: {}", args.join(" "), e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{}` exited with {}", args.join(" "), status))
+    }
+}
+
+fn apply_overrides(settings: &WineSettings, prefix_dir: &Path) -> Result<(), String> {
+    for (dll, mode) in DLL_OVERRIDES {
+        run_in_prefix(
+            settings,
+            prefix_dir,
+            &[
+                "reg",
+                "add",
+                r"HKCU\Software\Wine\DllOverrides",
+                "/v",
+                dll,
+                "/d",
+                mode,
+                "/f",
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn install_webview2(settings: &WineSettings, prefix_dir: &Path) -> Result<(), String> {
+    let installer_path = prefix_dir.join(WEBVIEW2_INSTALLER_FILE);
+
+    let bytes = reqwest::blocking::get(WEBVIEW2_INSTALLER_URL)
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.bytes())
+        .map_err(|e| format!("Failed to download WebView2 installer: {}", e))?;
+
+    fs::write(&installer_path, &bytes)
+        .map_err(|e| format!("Failed to save WebView2 installer: {}", e))?;
+
+    let installer = installer_path.to_string_lossy().to_string();
+    let result = run_in_prefix(settings, prefix_dir, &[&installer, "/silent", "/install"]);
+
+    fs::remove_file(&installer_path).ok();
+    result
+}
+
+fn install_fonts(settings: &WineSettings, prefix_dir: &Path) -> Result<(), String> {
+    // Proton ships its own font replacements, and winetricks would need to be
+    // pointed at the prefix inside Proton's compat data directory.
+    if settings.runner == WineRunner::Proton {
+        return Ok(());
+    }
+
+    let mut command = Command::new("winetricks");
+    command
+        .args(["-q", "corefonts"])
+        .env("WINEPREFIX", prefix_dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    if let Some(binary) = &settings.binary {
+        command.env("WINE", binary);
+    }
+
+    let status = command
+        .status()
+        .map_err(|e| format!("Failed to run winetricks: {}", e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("winetricks corefonts exited with {}", status))
+    }
+}
+
+fn save_health(prefix_dir: &Path, health: &PrefixHealth) {
+    match serde_json::to_string_pretty(health) {
+        Ok(contents) => {
+            if let Err(e) = fs::write(prefix_dir.join(HEALTH_FILE), contents) {
+                tracing::warn!("Failed to record Wine prefix health: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to serialize Wine prefix health: {}", e),
+    }
+}
+
+/// The recorded health of the prefix at `prefix_dir`.
+pub fn load_health(prefix_dir: &Path, runner: WineRunner) -> PrefixHealth {
+    fs::read_to_string(prefix_dir.join(HEALTH_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_else(|| PrefixHealth::missing(prefix_dir, runner))
+}
+
+/// Create the prefix from scratch. Blocking: `wineboot` and the WebView2
+/// installer can each take a while.
+fn create_prefix(settings: &WineSettings, prefix_dir: &Path) -> Result<PrefixHealth, String> {
+    tracing::info!("Creating Wine prefix at {:?}", prefix_dir);
+
+    fs::create_dir_all(prefix_dir)
+        .map_err(|e| format!("Failed to create Wine prefix directory: {}", e))?;
+
+    let mut health = PrefixHealth::missing(prefix_dir, settings.runner);
+
+    health.initialized =
+        health.record(run_in_prefix(settings, prefix_dir, &["wineboot", "--init"]));
+
+    if health.initialized {
+        health.overrides_applied = health.record(apply_overrides(settings, prefix_dir));
+        health.webview2_installed = health.record(install_webview2(settings, prefix_dir));
+        health.fonts_installed = health.record(install_fonts(settings, prefix_dir));
+    }
+
+    let health = health.finish();
+    save_health(prefix_dir, &health);
+
+    tracing::info!("Wine prefix setup finished: {:?}", health.status);
+    Ok(health)
+}
+
+/// Remove the prefix entirely. Returns whether there was anything to remove.
+pub fn delete_prefix(prefix_dir: &Path) -> Result<bool, String> {
+    if !prefix_dir.exists() {
+        return Ok(false);
+    }
+
+    tracing::info!("Deleting Wine prefix at {:?}", prefix_dir);
+    fs::remove_dir_all(prefix_dir).map_err(|e| format!("Failed to delete Wine prefix: {}", e))?;
+    Ok(true)
+}
+
+pub fn rebuild_prefix(settings: &WineSettings, prefix_dir: &Path) -> Result<PrefixHealth, String> {
+    delete_prefix(prefix_dir)?;
+    create_prefix(settings, prefix_dir)
+}
+
+/// Make sure a usable prefix exists, creating it on first use or when the
+/// configured runner has changed since it was built. A degraded prefix is
+/// kept as-is; players can rebuild it from settings.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn ensure_prefix(settings: &WineSettings, prefix_dir: &Path) -> Result<PrefixHealth, String> {
+    let health = load_health(prefix_dir, settings.runner);
+
+    let health = match health.status {
+        PrefixStatus::Ready | PrefixStatus::Degraded if health.runner == settings.runner => health,
+        PrefixStatus::Missing => create_prefix(settings, prefix_dir)?,
+        _ => rebuild_prefix(settings, prefix_dir)?,
+    };
+
+    if health.status == PrefixStatus::Broken {
+        return Err(format!(
+            "Wine prefix could not be set up: {}",
+            health.error.as_deref().unwrap_or("unknown error")
+        ));
+    }
+
+    Ok(health)
+}
@@ -0,0 +1,375 @@
+//! Discovery of BYOND's published stable and beta builds
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use super::ByondVersion;
+
+const VERSION_URL: &str = "https://www.byond.com/download/version.txt";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long fetched release info is reused before asking byond.com again.
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The builds published on each release channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReleaseChannels {
+    pub stable: ByondVersion,
+    pub beta: Option<ByondVersion>,
+}
+
+/// Whether a newer build than the newest installed one is published for a
+/// major version.
+#[derive(Debug, Clone, Serialize)]
+pub struct MajorUpdate {
+    pub major: u32,
+    pub installed: Option<ByondVersion>,
+    pub latest: ByondVersion,
+    pub update_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatestByondVersions {
+    #[serde(flatten)]
+    pub channels: ReleaseChannels,
+    pub fetched_at: DateTime<Utc>,
+    pub majors: Vec<MajorUpdate>,
+}
+
+/// Where release info comes from. Swappable so discovery can run offline.
+#[async_trait]
+pub trait ReleaseSource: Send + Sync {
+    /// Returns the raw contents of BYOND's `version.txt`
+    async fn fetch(&self) -> Result<String, String>;
+}
+
+pub struct HttpReleaseSource;
+
+#[async_trait]
+impl ReleaseSource for HttpReleaseSource {
+    async fn fetch(&self) -> Result<String, String> {
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        let response = client
+            .get(VERSION_URL)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch BYOND versions: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP error: {}", response.status()));
+        }
+
+        response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read BYOND versions: {}", e))
+    }
+}
+
+/// Serves fixed release info, set through `CM_LAUNCHER_BYOND_VERSIONS` as
+/// `version.txt` contents with `;` between lines, e.g. `515.1647;516.1667`.
+#[cfg(feature = "dev")]
+pub struct StaticReleaseSource(String);
+
+#[cfg(feature = "dev")]
+impl StaticReleaseSource {
+    pub fn from_env() -> Option<Self> {
+        let contents = std::env::var("CM_LAUNCHER_BYOND_VERSIONS").ok()?;
+        Some(Self(contents.replace(';', "\n")))
+    }
+}
+
+#[cfg(feature = "dev")]
+#[async_trait]
+impl ReleaseSource for StaticReleaseSource {
+    async fn fetch(&self) -> Result<String, String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Parse `version.txt`: the stable build on the first line, then the beta
+/// build when one is out.
+pub fn parse_version_txt(contents: &str) -> Result<ReleaseChannels, String> {
+    let mut lines = contents.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    let stable = lines.next().ok_or("BYOND version list is empty")?.parse()?;
+    let beta = lines
+        .next()
+        .map(str::parse::<ByondVersion>)
+        .transpose()?
+        .filter(|beta| *beta > stable);
+
+    Ok(ReleaseChannels { stable, beta })
+}
+
+/// Compare the published builds against what's installed, per major.
+pub fn find_updates(channels: &ReleaseChannels, installed: &[ByondVersion]) -> Vec<MajorUpdate> {
+    let published = [Some(channels.stable), channels.beta];
+    let majors: BTreeSet<u32> = published.iter().flatten().map(|v| v.major).collect();
+
+    majors
+        .into_iter()
+        .filter_map(|major| {
+            let latest = published
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|v| v.major == major)
+                .max()?;
+            let installed = installed.iter().copied().filter(|v| v.major == major).max();
+
+            Some(MajorUpdate {
+                major,
+                installed,
+                latest,
+                update_available: installed.is_none_or(|installed| latest > installed),
+            })
+        })
+        .collect()
+}
+
+/// Caches release info from a `ReleaseSource`.
+pub struct ReleaseCache {
+    source: Box<dyn ReleaseSource>,
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, DateTime<Utc>, ReleaseChannels)>>,
+}
+
+impl ReleaseCache {
+    pub fn new(source: Box<dyn ReleaseSource>) -> Self {
+        Self {
+            source,
+            ttl: CACHE_TTL,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// The HTTP source, or the static one in dev builds when it's configured.
+    pub fn from_env() -> Self {
+        #[cfg(feature = "dev")]
+        if let Some(source) = StaticReleaseSource::from_env() {
+            return Self::new(Box::new(source));
+        }
+
+        Self::new(Box::new(HttpReleaseSource))
+    }
+
+    /// The published builds and when they were fetched. Cached results are
+    /// reused for an hour unless `refresh` is set; if a fetch fails, stale
+    /// results are returned rather than nothing.
+    pub async fn channels(
+        &self,
+        refresh: bool,
+    ) -> Result<(ReleaseChannels, DateTime<Utc>), String> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some((fetched, fetched_at, channels)) = *cached {
+            if !refresh && fetched.elapsed() < self.ttl {
+                return Ok((channels, fetched_at));
+            }
+        }
+
+        match self
+            .source
+            .fetch()
+            .await
+            .and_then(|c| parse_version_txt(&c))
+        {
+            Ok(channels) => {
+                let fetched_at = Utc::now();
+                *cached = Some((Instant::now(), fetched_at, channels));
+                Ok((channels, fetched_at))
+            }
+            Err(e) => match *cached {
+                Some((_, fetched_at, channels)) => {
+                    tracing::warn!("Failed to refresh BYOND versions, using cached: {}", e);
+                    Ok((channels, fetched_at))
+                }
+                None => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn version(major: u32, build: u32) -> ByondVersion {
+        ByondVersion { major, build }
+    }
+
+    /// Serves whatever `response` currently holds and counts fetches.
+    #[derive(Clone)]
+    struct MemorySource {
+        response: Arc<std::sync::Mutex<Result<String, String>>>,
+        fetches: Arc<AtomicUsize>,
+    }
+
+    impl MemorySource {
+        fn new(contents: &str) -> Self {
+            Self {
+                response: Arc::new(std::sync::Mutex::new(Ok(contents.to_string()))),
+                fetches: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn set(&self, response: Result<&str, &str>) {
+            *self.response.lock().unwrap() = response.map(str::to_string).map_err(str::to_string);
+        }
+
+        fn fetches(&self) -> usize {
+            self.fetches.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl ReleaseSource for MemorySource {
+        async fn fetch(&self) -> Result<String, String> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.response.lock().unwrap().clone()
+        }
+    }
+
+    fn cache(source: &MemorySource) -> ReleaseCache {
+        ReleaseCache::new(Box::new(source.clone()))
+    }
+
+    #[test]
+    fn parses_stable_and_beta() {
+        let channels = parse_version_txt("515.1647\n516.1667\n").unwrap();
+        assert_eq!(channels.stable, version(515, 1647));
+        assert_eq!(channels.beta, Some(version(516, 1667)));
+    }
+
+    #[test]
+    fn parses_stable_only() {
+        let channels = parse_version_txt("  516.1667\r\n\n").unwrap();
+        assert_eq!(channels.stable, version(516, 1667));
+        assert_eq!(channels.beta, None);
+    }
+
+    #[test]
+    fn drops_a_beta_older_than_stable() {
+        let channels = parse_version_txt("516.1667\n516.1660").unwrap();
+        assert_eq!(channels.beta, None);
+    }
+
+    #[test]
+    fn rejects_bad_version_txt() {
+        assert!(parse_version_txt("").is_err());
+        assert!(parse_version_txt("\n  \n").is_err());
+        assert!(parse_version_txt("<html>").is_err());
+        assert!(parse_version_txt("516.1667\n../x").is_err());
+    }
+
+    #[test]
+    fn finds_updates_per_major() {
+        let channels = ReleaseChannels {
+            stable: version(515, 1647),
+            beta: Some(version(516, 1667)),
+        };
+        let installed = [version(515, 1647), version(515, 1640), version(516, 1660)];
+
+        let updates = find_updates(&channels, &installed);
+        assert_eq!(updates.len(), 2);
+
+        assert_eq!(updates[0].major, 515);
+        assert_eq!(updates[0].installed, Some(version(515, 1647)));
+        assert!(!updates[0].update_available);
+
+        assert_eq!(updates[1].major, 516);
+        assert_eq!(updates[1].installed, Some(version(516, 1660)));
+        assert_eq!(updates[1].latest, version(516, 1667));
+        assert!(updates[1].update_available);
+    }
+
+    #[test]
+    fn finds_updates_for_missing_majors() {
+        let channels = ReleaseChannels {
+            stable: version(516, 1667),
+            beta: None,
+        };
+
+        let updates = find_updates(&channels, &[version(515, 1647)]);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].installed, None);
+        assert!(updates[0].update_available);
+    }
+
+    #[tokio::test]
+    async fn reuses_cached_channels_within_ttl() {
+        let source = MemorySource::new("516.1667");
+        let cache = cache(&source);
+
+        let (first, fetched_at) = cache.channels(false).await.unwrap();
+        source.set(Ok("516.1668"));
+        let (second, cached_at) = cache.channels(false).await.unwrap();
+
+        assert_eq!(source.fetches(), 1);
+        assert_eq!(first, second);
+        assert_eq!(fetched_at, cached_at);
+    }
+
+    #[tokio::test]
+    async fn fetches_again_after_ttl() {
+        let source = MemorySource::new("516.1667");
+        let mut cache = cache(&source);
+        cache.ttl = Duration::ZERO;
+
+        cache.channels(false).await.unwrap();
+        source.set(Ok("516.1668"));
+        let (channels, _) = cache.channels(false).await.unwrap();
+
+        assert_eq!(source.fetches(), 2);
+        assert_eq!(channels.stable, version(516, 1668));
+    }
+
+    #[tokio::test]
+    async fn refresh_skips_the_cache() {
+        let source = MemorySource::new("516.1667");
+        let cache = cache(&source);
+
+        cache.channels(false).await.unwrap();
+        source.set(Ok("516.1668"));
+        let (channels, _) = cache.channels(true).await.unwrap();
+
+        assert_eq!(source.fetches(), 2);
+        assert_eq!(channels.stable, version(516, 1668));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_stale_channels_on_error() {
+        let source = MemorySource::new("516.1667");
+        let cache = cache(&source);
+
+        let (fresh, fetched_at) = cache.channels(false).await.unwrap();
+        source.set(Err("offline"));
+        let (stale, stale_at) = cache.channels(true).await.unwrap();
+
+        assert_eq!(source.fetches(), 2);
+        assert_eq!(fresh, stale);
+        assert_eq!(fetched_at, stale_at);
+
+        source.set(Ok("garbage"));
+        assert_eq!(cache.channels(true).await.unwrap().0, fresh);
+    }
+
+    #[tokio::test]
+    async fn errors_without_anything_cached() {
+        let source = MemorySource::new("516.1667");
+        source.set(Err("offline"));
+        let cache = cache(&source);
+
+        assert_eq!(cache.channels(false).await, Err("offline".to_string()));
+    }
+}
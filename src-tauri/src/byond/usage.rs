@@ -0,0 +1,92 @@
+//! Install and launch times, disk usage, and pruning of installed BYOND versions
+
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use super::ByondVersion;
+
+/// Name of the file in a version directory holding when it was last launched.
+const LAST_LAUNCHED_FILE: &str = "last_launched";
+/// Name of the file in a version directory holding when it was installed.
+const INSTALLED_AT_FILE: &str = "installed_at";
+
+fn read_timestamp(path: &Path) -> Option<DateTime<Utc>> {
+    let contents = fs::read_to_string(path).ok()?;
+    DateTime::parse_from_rfc3339(contents.trim())
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+pub fn record_install(version_dir: &Path) -> Result<(), String> {
+    fs::write(version_dir.join(INSTALLED_AT_FILE), Utc::now().to_rfc3339())
+        .map_err(|e| format!("Failed to record install time: {}", e))
+}
+
+/// When the version was installed. Installs from before this was recorded
+/// fall back to the directory's modification time.
+pub fn installed_at(version_dir: &Path) -> Option<DateTime<Utc>> {
+    read_timestamp(&version_dir.join(INSTALLED_AT_FILE)).or_else(|| {
+        fs::metadata(version_dir)
+            .and_then(|m| m.modified())
+            .ok()
+            .map(DateTime::<Utc>::from)
+    })
+}
+
+pub fn record_launch(version_dir: &Path) {
+    if let Err(e) = fs::write(
+        version_dir.join(LAST_LAUNCHED_FILE),
+        Utc::now().to_rfc3339(),
+    ) {
+        tracing::warn!("Failed to record BYOND launch time: {}", e);
+    }
+}
+
+pub fn last_launched(version_dir: &Path) -> Option<DateTime<Utc>> {
+    read_timestamp(&version_dir.join(LAST_LAUNCHED_FILE))
+}
+
+/// Total size of the files under `path`. Unreadable entries are skipped.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_type = entry.file_type().ok()?;
+            if file_type.is_dir() {
+                Some(dir_size(&entry.path()))
+            } else if file_type.is_file() {
+                entry.metadata().ok().map(|m| m.len())
+            } else {
+                None
+            }
+        })
+        .sum()
+}
+
+/// Pick the versions to remove: everything except the `keep_recent` most
+/// recently launched and anything in `protected`. Versions that were never
+/// launched count as older than any that were, newest build first.
+pub fn select_for_pruning(
+    mut installed: Vec<(ByondVersion, Option<DateTime<Utc>>)>,
+    keep_recent: usize,
+    protected: &HashSet<ByondVersion>,
+) -> Vec<ByondVersion> {
+    installed.sort_by(|(a_version, a_launched), (b_version, b_launched)| {
+        b_launched
+            .cmp(a_launched)
+            .then_with(|| b_version.cmp(a_version))
+    });
+
+    installed
+        .into_iter()
+        .skip(keep_recent)
+        .map(|(version, _)| version)
+        .filter(|version| !protected.contains(version))
+        .collect()
+}
@@ -0,0 +1,242 @@
+//! SHA-256 verification of downloaded BYOND archives
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::ByondVersion;
+
+const MANIFEST_URL: &str = "https://db.cm-ss13.com/api/Byond/Hashes";
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Name of the file in a version directory holding the verified archive hash.
+pub const HASH_FILE: &str = "byond.sha256";
+
+/// Hashes shipped with the launcher, as a map of version to sha256 of the
+/// byond.com archive, checked when the remote manifest is unreachable or
+/// doesn't list a version. Add entries with `tools/pin_byond_hash.sh`.
+const PINNED_HASHES_JSON: &str = include_str!("../../byond_hashes.json");
+
+#[derive(Debug, Deserialize)]
+struct HashManifest {
+    versions: HashMap<String, String>,
+}
+
+async fn fetch_remote_hash(version: ByondVersion) -> Result<Option<String>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(MANIFEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let response = client
+        .get(MANIFEST_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch hash manifest: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("HTTP error: {}", response.status()));
+    }
+
+    let manifest: HashManifest = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse hash manifest: {}", e))?;
+
+    Ok(manifest.versions.get(&version.to_string()).cloned())
+}
+
+fn pinned_hashes() -> &'static HashMap<String, String> {
+    static PINNED: OnceLock<HashMap<String, String>> = OnceLock::new();
+    PINNED.get_or_init(|| {
+        serde_json::from_str(PINNED_HASHES_JSON).unwrap_or_else(|e| {
+            tracing::error!("Failed to parse pinned BYOND hashes: {}", e);
+            HashMap::new()
+        })
+    })
+}
+
+fn pinned_hash(version: ByondVersion) -> Option<String> {
+    pinned_hashes().get(&version.to_string()).cloned()
+}
+
+/// Look up the expected hash for `version`, preferring the remote manifest so
+/// builds newer than this launcher can still be verified.
+pub async fn expected_hash(version: ByondVersion) -> Option<String> {
+    match fetch_remote_hash(version).await {
+        Ok(Some(hash)) => return Some(hash),
+        Ok(None) => {
+            tracing::debug!("BYOND {} not listed in remote hash manifest", version);
+        }
+        Err(e) => {
+            tracing::warn!("Could not fetch BYOND hash manifest: {}", e);
+        }
+    }
+
+    pinned_hash(version)
+}
+
+pub fn hash_file(path: &Path) -> Result<String, String> {
+    let mut file =
+        fs::File::open(path).map_err(|e| format!("Failed to open file for hashing: {}", e))?;
+
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read file for hashing: {}", e)),
+        };
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hash the downloaded archive and compare it against the known hash for
+/// `version`. Returns the hash once it has matched, or `None` for an archive
+/// with no known hash. Those are installed with a warning if
+/// `allow_unverified` is set, and rejected otherwise.
+pub async fn verify_archive(
+    version: ByondVersion,
+    zip_path: &Path,
+    allow_unverified: bool,
+) -> Result<Option<String>, String> {
+    let expected = expected_hash(version).await;
+    check_archive(version, zip_path, expected.as_deref(), allow_unverified).await
+}
+
+async fn check_archive(
+    version: ByondVersion,
+    zip_path: &Path,
+    expected: Option<&str>,
+    allow_unverified: bool,
+) -> Result<Option<String>, String> {
+    if expected.is_none() && !allow_unverified {
+        tracing::error!("No known hash for BYOND {}, refusing to install", version);
+        return Err(format!(
+            "BYOND {} can't be verified. Allow unverified BYOND versions in settings to install it anyway.",
+            version
+        ));
+    }
+
+    let path: PathBuf = zip_path.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || hash_file(&path))
+        .await
+        .map_err(|e| format!("Hashing task failed: {}", e))??;
+
+    match expected {
+        Some(expected) if !expected.eq_ignore_ascii_case(&actual) => {
+            tracing::error!(
+                "BYOND {} archive hash mismatch: expected {}, got {}",
+                version,
+                expected,
+                actual
+            );
+            Err(format!(
+                "Downloaded BYOND {} failed integrity check. Please try again.",
+                version
+            ))
+        }
+        Some(_) => {
+            tracing::info!("BYOND {} archive verified ({})", version, actual);
+            Ok(Some(actual))
+        }
+        None => {
+            tracing::warn!(
+                "No known hash for BYOND {}, installing unverified archive ({})",
+                version,
+                actual
+            );
+            Ok(None)
+        }
+    }
+}
+
+pub fn record_hash(version_dir: &Path, hash: &str) -> Result<(), String> {
+    fs::write(version_dir.join(HASH_FILE), hash)
+        .map_err(|e| format!("Failed to record archive hash: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// sha256 of `b"byond"`
+    const CONTENTS_HASH: &str = "9ca5b323b8bd6abc494fa11fb1825052fc6471cc33234235d23a37edb61411d2";
+
+    fn write_archive(contents: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("verify-{}.zip", uuid::Uuid::new_v4()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn version() -> ByondVersion {
+        "516.1667".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_matching_hash() {
+        let path = write_archive(b"byond");
+
+        let expected = CONTENTS_HASH.to_uppercase();
+        let result = check_archive(version(), &path, Some(&expected), false).await;
+
+        assert_eq!(result, Ok(Some(CONTENTS_HASH.to_string())));
+        fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn rejects_mismatched_hash() {
+        let path = write_archive(b"tampered");
+
+        let result = check_archive(version(), &path, Some(CONTENTS_HASH), false).await;
+        assert!(result.unwrap_err().contains("failed integrity check"));
+
+        // Opting in to unverified versions doesn't excuse a mismatch
+        let result = check_archive(version(), &path, Some(CONTENTS_HASH), true).await;
+        assert!(result.is_err());
+        fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_version_by_default() {
+        let path = write_archive(b"byond");
+
+        let result = check_archive(version(), &path, None, false).await;
+        assert!(result.unwrap_err().contains("can't be verified"));
+        fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn allows_unknown_version_when_opted_in() {
+        let path = write_archive(b"byond");
+
+        // Allowed, but not reported as verified
+        let result = check_archive(version(), &path, None, true).await;
+        assert_eq!(result, Ok(None));
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn unpinned_version_has_no_hash() {
+        assert_eq!(pinned_hash("1.1".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn pinned_hashes_parse() {
+        for (version, hash) in pinned_hashes() {
+            assert!(version.parse::<ByondVersion>().is_ok(), "{}", version);
+            assert_eq!(hash.len(), 64, "{}", version);
+            assert!(hash.chars().all(|c| c.is_ascii_hexdigit()), "{}", version);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Parsed BYOND version numbers
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A BYOND version such as `516.1667`. Both parts are plain numbers, so a
+/// version is always safe to use as a path component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByondVersion {
+    pub major: u32,
+    pub build: u32,
+}
+
+/// Leading zeros are rejected rather than dropped, since `516.01` would
+/// otherwise quietly download `516.1`.
+fn parse_component(part: &str) -> Option<u32> {
+    if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    if part.len() > 1 && part.starts_with('0') {
+        return None;
+    }
+    part.parse().ok()
+}
+
+impl FromStr for ByondVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid BYOND version format: {}", s);
+
+        let (major, build) = s.trim().split_once('.').ok_or_else(invalid)?;
+        let major = parse_component(major).ok_or_else(invalid)?;
+        let build = parse_component(build).ok_or_else(invalid)?;
+
+        Ok(Self { major, build })
+    }
+}
+
+impl fmt::Display for ByondVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.build)
+    }
+}
+
+impl Serialize for ByondVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByondVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Deserialize an optional version from an external API, treating a malformed
+/// value as missing rather than failing the whole response.
+pub fn deserialize_lenient<'de, D>(deserializer: D) -> Result<Option<ByondVersion>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.and_then(|s| match s.parse() {
+        Ok(version) => Some(version),
+        Err(e) => {
+            tracing::warn!("{}", e);
+            None
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u32, build: u32) -> ByondVersion {
+        ByondVersion { major, build }
+    }
+
+    #[test]
+    fn parses_valid_versions() {
+        assert_eq!("516.1667".parse(), Ok(version(516, 1667)));
+        assert_eq!("0.0".parse(), Ok(version(0, 0)));
+        assert_eq!(" 515.1647\n".parse(), Ok(version(515, 1647)));
+    }
+
+    #[test]
+    fn rejects_path_traversal() {
+        for input in [
+            "../x",
+            "..",
+            "516/../1667",
+            "516.1667/..",
+            "516.1667\\x",
+            "./516.1667",
+        ] {
+            assert!(input.parse::<ByondVersion>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_empty_and_missing_parts() {
+        for input in ["", ".", "516.", ".1667", "516", "516..1667", "516.1667.1"] {
+            assert!(input.parse::<ByondVersion>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_signs() {
+        for input in ["+516.1667", "-516.1667", "516.+1667", "516.-1667"] {
+            assert!(input.parse::<ByondVersion>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!("4294967296.1".parse::<ByondVersion>().is_err());
+        assert!("516.99999999999".parse::<ByondVersion>().is_err());
+        assert_eq!("4294967295.1".parse(), Ok(version(u32::MAX, 1)));
+    }
+
+    #[test]
+    fn rejects_inner_whitespace() {
+        for input in ["516 .1667", "516. 1667", "5 16.1667", "516.1667 x"] {
+            assert!(input.parse::<ByondVersion>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_leading_zeros() {
+        for input in ["516.01", "0516.1667", "516.0001667", "00.1"] {
+            assert!(input.parse::<ByondVersion>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let v = version(516, 1667);
+        assert_eq!(v.to_string().parse(), Ok(v));
+        assert_eq!(serde_json::to_string(&v).unwrap(), "\"516.1667\"");
+        assert_eq!(
+            serde_json::from_str::<ByondVersion>("\"516.1667\"").unwrap(),
+            v
+        );
+        assert!(serde_json::from_str::<ByondVersion>("\"../x\"").is_err());
+    }
+
+    #[test]
+    fn orders_numerically() {
+        assert!(version(516, 1667) > version(516, 999));
+        assert!(version(516, 1) > version(515, 1647));
+        assert!(version(515, 1647) < version(515, 1648));
+
+        let mut versions = vec![version(516, 10), version(515, 2000), version(516, 9)];
+        versions.sort();
+        assert_eq!(
+            versions,
+            [version(515, 2000), version(516, 9), version(516, 10)]
+        );
+    }
+
+    #[derive(Deserialize)]
+    struct Server {
+        #[serde(default, deserialize_with = "deserialize_lenient")]
+        version: Option<ByondVersion>,
+    }
+
+    fn lenient(json: &str) -> Option<ByondVersion> {
+        serde_json::from_str::<Server>(json).unwrap().version
+    }
+
+    #[test]
+    fn lenient_drops_bad_values() {
+        assert_eq!(
+            lenient(r#"{"version": "516.1667"}"#),
+            Some(version(516, 1667))
+        );
+        assert_eq!(lenient(r#"{"version": "../x"}"#), None);
+        assert_eq!(lenient(r#"{"version": "516.01"}"#), None);
+        assert_eq!(lenient(r#"{"version": ""}"#), None);
+        assert_eq!(lenient(r#"{"version": null}"#), None);
+        assert_eq!(lenient("{}"), None);
+    }
+}
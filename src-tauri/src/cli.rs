@@ -0,0 +1,198 @@
+//! Command-line arguments, so shortcuts and scripts can launch straight into
+//! a server
+
+use tauri::AppHandle;
+
+use crate::autoconnect::{start_autoconnect, AutoConnectOptions};
+use crate::settings::AuthMode;
+
+#[derive(Debug, Clone, Default)]
+pub struct CliArgs {
+    /// `--connect "<server name>"`
+    pub connect: Option<String>,
+    /// `--relay <id>`, used instead of the fastest relay
+    pub relay: Option<String>,
+    /// `--auth-mode <cm_ss13|byond|steam>`, used instead of the saved mode
+    pub auth_mode: Option<AuthMode>,
+    /// `--minimized`
+    pub minimized: bool,
+}
+
+fn parse_auth_mode(value: &str) -> Option<AuthMode> {
+    match value.to_lowercase().replace('-', "_").as_str() {
+        "cm_ss13" | "cmss13" => Some(AuthMode::CmSs13),
+        "byond" => Some(AuthMode::Byond),
+        "steam" => Some(AuthMode::Steam),
+        _ => None,
+    }
+}
+
+impl CliArgs {
+    /// Parse arguments, not including the program name. Accepts both
+    /// `--flag value` and `--flag=value`. Anything unrecognised is logged and
+    /// skipped, as the OS may pass other arguments such as deep links. A value
+    /// can't start with `--`, so a flag missing its value doesn't swallow the
+    /// next one.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                continue;
+            }
+
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            if flag == "--minimized" {
+                parsed.minimized = true;
+                continue;
+            }
+
+            if !matches!(flag.as_str(), "--connect" | "--relay" | "--auth-mode") {
+                tracing::warn!("Ignoring unknown argument: {}", flag);
+                continue;
+            }
+
+            let value = match inline_value {
+                Some(value) => Some(value),
+                None => args.next_if(|next| !next.starts_with("--")),
+            };
+            let Some(value) = value.filter(|v| !v.is_empty() && !v.starts_with("--")) else {
+                tracing::warn!("Missing value for {}", flag);
+                continue;
+            };
+
+            match flag.as_str() {
+                "--connect" => parsed.connect = Some(value),
+                "--relay" => parsed.relay = Some(value),
+                _ => match parse_auth_mode(&value) {
+                    Some(mode) => parsed.auth_mode = Some(mode),
+                    None => tracing::warn!("Unknown auth mode: {}", value),
+                },
+            }
+        }
+
+        parsed
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Start connecting if `--connect` was given. Returns whether it was.
+    pub fn start_autoconnect(&self, handle: &AppHandle) -> bool {
+        let Some(server_name) = self.connect.clone() else {
+            if self.relay.is_some() || self.auth_mode.is_some() {
+                tracing::warn!("--relay and --auth-mode only apply with --connect");
+            }
+            return false;
+        };
+
+        tracing::info!("Command-line connect to: {}", server_name);
+        let options = AutoConnectOptions {
+            relay: self.relay.clone(),
+            auth_mode: self.auth_mode,
+        };
+        start_autoconnect(handle.clone(), server_name, options);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CliArgs {
+        CliArgs::parse(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn parses_separate_values() {
+        let args = parse(&[
+            "--connect",
+            "CM Main",
+            "--relay",
+            "eu",
+            "--auth-mode",
+            "byond",
+        ]);
+        assert_eq!(args.connect.as_deref(), Some("CM Main"));
+        assert_eq!(args.relay.as_deref(), Some("eu"));
+        assert_eq!(args.auth_mode, Some(AuthMode::Byond));
+        assert!(!args.minimized);
+    }
+
+    #[test]
+    fn parses_inline_values() {
+        let args = parse(&[
+            "--connect=CM Main",
+            "--relay=eu",
+            "--auth-mode=cm-ss13",
+            "--minimized",
+        ]);
+        assert_eq!(args.connect.as_deref(), Some("CM Main"));
+        assert_eq!(args.relay.as_deref(), Some("eu"));
+        assert_eq!(args.auth_mode, Some(AuthMode::CmSs13));
+        assert!(args.minimized);
+    }
+
+    #[test]
+    fn inline_value_keeps_later_equals_signs() {
+        let args = parse(&["--connect=a=b"]);
+        assert_eq!(args.connect.as_deref(), Some("a=b"));
+    }
+
+    #[test]
+    fn missing_value_does_not_swallow_next_flag() {
+        let args = parse(&["--relay", "--minimized"]);
+        assert_eq!(args.relay, None);
+        assert!(args.minimized);
+
+        let args = parse(&["--connect", "--relay", "eu"]);
+        assert_eq!(args.connect, None);
+        assert_eq!(args.relay.as_deref(), Some("eu"));
+    }
+
+    #[test]
+    fn rejects_empty_and_flag_like_inline_values() {
+        let args = parse(&["--relay=", "--connect=--minimized"]);
+        assert_eq!(args.relay, None);
+        assert_eq!(args.connect, None);
+        assert!(!args.minimized);
+    }
+
+    #[test]
+    fn missing_value_at_the_end() {
+        let args = parse(&["--minimized", "--connect"]);
+        assert_eq!(args.connect, None);
+        assert!(args.minimized);
+    }
+
+    #[test]
+    fn parses_auth_modes() {
+        assert_eq!(parse_auth_mode("cm_ss13"), Some(AuthMode::CmSs13));
+        assert_eq!(parse_auth_mode("CMSS13"), Some(AuthMode::CmSs13));
+        assert_eq!(parse_auth_mode("Steam"), Some(AuthMode::Steam));
+        assert_eq!(parse_auth_mode("byond"), Some(AuthMode::Byond));
+        assert_eq!(parse_auth_mode("oauth"), None);
+        assert_eq!(parse_auth_mode(""), None);
+    }
+
+    #[test]
+    fn ignores_unknown_auth_modes() {
+        let args = parse(&["--auth-mode", "oauth", "--connect", "Main"]);
+        assert_eq!(args.auth_mode, None);
+        assert_eq!(args.connect.as_deref(), Some("Main"));
+    }
+
+    #[test]
+    fn skips_unknown_arguments() {
+        let args = parse(&["cm-ss13://connect/Main", "--verbose", "--connect", "Main"]);
+        assert_eq!(args.connect.as_deref(), Some("Main"));
+        assert_eq!(args.relay, None);
+    }
+}
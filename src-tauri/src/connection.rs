@@ -0,0 +1,260 @@
+//! Tracks where a connection attempt is, from auth through to the game
+//! connecting back to the control server
+
+use serde::Serialize;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::watch;
+
+use crate::byond::ByondVersion;
+
+const STATE_EVENT: &str = "connection-state";
+const CANCELLED_MESSAGE: &str = "Connection cancelled";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConnectionState {
+    Idle,
+    Authenticating {
+        server_name: String,
+    },
+    Installing {
+        server_name: String,
+        version: ByondVersion,
+    },
+    Launching {
+        server_name: String,
+        version: ByondVersion,
+    },
+    Connected {
+        server_name: String,
+    },
+    Failed {
+        server_name: String,
+        error: String,
+    },
+    Cancelled {
+        server_name: String,
+    },
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: ConnectionState,
+    /// Whether a `ConnectionAttempt` is alive. Only one may be at a time.
+    active: bool,
+    /// Signals the live attempt to stop.
+    cancel: Option<watch::Sender<bool>>,
+}
+
+/// Owns the current `ConnectionState`. Every change is emitted to the
+/// frontend as a `connection-state` event.
+#[derive(Debug)]
+pub struct ConnectionStateMachine {
+    inner: Mutex<Inner>,
+}
+
+impl Default for ConnectionStateMachine {
+    fn default() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state: ConnectionState::Idle,
+                active: false,
+                cancel: None,
+            }),
+        }
+    }
+}
+
+impl ConnectionStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.inner.lock().unwrap().state.clone()
+    }
+
+    fn set(&self, app: &AppHandle, inner: &mut Inner, state: ConnectionState) {
+        tracing::debug!("Connection state: {:?}", state);
+        inner.state = state.clone();
+        app.emit(STATE_EVENT, state).ok();
+    }
+
+    /// Start a connection attempt to `server_name`, or return `None` if one is
+    /// already under way.
+    pub fn begin(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        server_name: &str,
+    ) -> Option<ConnectionAttempt> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.active {
+            return None;
+        }
+
+        let (cancel, cancelled) = watch::channel(false);
+        inner.active = true;
+        inner.cancel = Some(cancel);
+        self.set(
+            app,
+            &mut inner,
+            ConnectionState::Authenticating {
+                server_name: server_name.to_string(),
+            },
+        );
+
+        Some(ConnectionAttempt {
+            machine: Arc::clone(self),
+            app: app.clone(),
+            server_name: server_name.to_string(),
+            cancelled,
+            finished: false,
+        })
+    }
+
+    /// Ask the live attempt to stop. Returns whether there was one.
+    pub fn cancel(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        match &inner.cancel {
+            Some(cancel) if inner.active => {
+                tracing::info!("Cancelling connection attempt");
+                cancel.send(true).ok();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The game reached the control server.
+    pub fn game_connected(&self, app: &AppHandle, server_name: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        self.set(
+            app,
+            &mut inner,
+            ConnectionState::Connected {
+                server_name: server_name.to_string(),
+            },
+        );
+    }
+
+    /// The game exited. Leaves the state alone if a new attempt has already
+    /// started, e.g. when the game was closed to restart it.
+    pub fn game_exited(&self, app: &AppHandle) {
+        let mut inner = self.inner.lock().unwrap();
+        let running = matches!(
+            inner.state,
+            ConnectionState::Launching { .. } | ConnectionState::Connected { .. }
+        );
+        if !inner.active && running {
+            self.set(app, &mut inner, ConnectionState::Idle);
+        }
+    }
+}
+
+/// A single connection attempt. Dropping it before `launched` or `fail` is
+/// called, including on a panic, marks the attempt as failed so the state
+/// can never be left stuck mid-connect.
+pub struct ConnectionAttempt {
+    machine: Arc<ConnectionStateMachine>,
+    app: AppHandle,
+    server_name: String,
+    cancelled: watch::Receiver<bool>,
+    finished: bool,
+}
+
+impl ConnectionAttempt {
+    fn transition(&self, state: ConnectionState) {
+        let mut inner = self.machine.inner.lock().unwrap();
+        self.machine.set(&self.app, &mut inner, state);
+    }
+
+    fn finish(&mut self, state: ConnectionState) {
+        let mut inner = self.machine.inner.lock().unwrap();
+        inner.active = false;
+        inner.cancel = None;
+        self.machine.set(&self.app, &mut inner, state);
+        self.finished = true;
+    }
+
+    pub fn app(&self) -> &AppHandle {
+        &self.app
+    }
+
+    pub fn server_name(&self) -> &str {
+        &self.server_name
+    }
+
+    pub fn installing(&self, version: ByondVersion) {
+        self.transition(ConnectionState::Installing {
+            server_name: self.server_name.clone(),
+            version,
+        });
+    }
+
+    pub fn launching(&self, version: ByondVersion) {
+        self.transition(ConnectionState::Launching {
+            server_name: self.server_name.clone(),
+            version,
+        });
+    }
+
+    /// DreamSeeker is running. The state stays at `Launching` until the game
+    /// connects back, but another attempt may now start.
+    pub fn launched(mut self) {
+        let mut inner = self.machine.inner.lock().unwrap();
+        inner.active = false;
+        inner.cancel = None;
+        self.finished = true;
+    }
+
+    /// Run `future` unless the attempt is cancelled first, in which case
+    /// `future` is dropped and an error returned.
+    pub async fn run<F: Future>(&self, future: F) -> Result<F::Output, String> {
+        let mut cancelled = self.cancelled.clone();
+        tokio::select! {
+            output = future => Ok(output),
+            Ok(_) = cancelled.wait_for(|cancelled| *cancelled) => {
+                Err(CANCELLED_MESSAGE.to_string())
+            }
+        }
+    }
+
+    /// Record that the attempt stopped because it was cancelled.
+    pub fn cancelled(mut self) {
+        self.finish(ConnectionState::Cancelled {
+            server_name: self.server_name.clone(),
+        });
+    }
+
+    pub fn fail(mut self, error: &str) {
+        self.finish(ConnectionState::Failed {
+            server_name: self.server_name.clone(),
+            error: error.to_string(),
+        });
+    }
+}
+
+impl Drop for ConnectionAttempt {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.finish(ConnectionState::Failed {
+                server_name: self.server_name.clone(),
+                error: "Connection attempt was interrupted".to_string(),
+            });
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_connection_state(
+    state: tauri::State<'_, Arc<ConnectionStateMachine>>,
+) -> ConnectionState {
+    state.state()
+}
+
+#[tauri::command]
+pub fn cancel_connection(state: tauri::State<'_, Arc<ConnectionStateMachine>>) -> bool {
+    state.cancel()
+}
@@ -5,6 +5,7 @@ use tauri::{Emitter, Manager};
 use tiny_http::{Response, Server};
 use url::Url;
 
+use crate::connection::ConnectionStateMachine;
 use crate::presence::{ConnectionParams, PresenceManager};
 
 fn cors_headers() -> Vec<tiny_http::Header> {
@@ -129,6 +130,9 @@ impl ControlServer {
             if !game_connected.swap(true, Ordering::SeqCst) {
                 tracing::info!("Game connected to control server");
                 if let Some(session) = presence_manager.get_game_session() {
+                    if let Some(machine) = app_handle.try_state::<Arc<ConnectionStateMachine>>() {
+                        machine.game_connected(&app_handle, &session.server_name);
+                    }
                     app_handle.emit("game-connected", &session.server_name).ok();
                 }
             }
@@ -288,7 +292,7 @@ impl ControlServer {
     }
 }
 
-async fn refresh_auth_token(
+pub(crate) async fn refresh_auth_token(
     #[allow(unused_variables)] app_handle: &tauri::AppHandle,
     mut params: ConnectionParams,
 ) -> Result<ConnectionParams, String> {
@@ -0,0 +1,130 @@
+//! `cm-ss13://` links, which open the launcher and connect to a server
+
+use percent_encoding::percent_decode_str;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+use crate::autoconnect::{start_autoconnect, AutoConnectOptions};
+
+const SCHEME: &str = "cm-ss13";
+
+/// The server name in a `cm-ss13://connect/<server>` link.
+pub fn parse_connect_link(url: &Url) -> Option<String> {
+    if url.scheme() != SCHEME || url.host_str() != Some("connect") {
+        return None;
+    }
+
+    let segment = url.path_segments()?.find(|s| !s.is_empty())?;
+    let server_name = percent_decode_str(segment).decode_utf8().ok()?;
+    let server_name = server_name.trim();
+
+    (!server_name.is_empty()).then(|| server_name.to_string())
+}
+
+/// Connect to the server in the first link that names one. Returns whether
+/// a connection was started.
+fn handle_urls(handle: &AppHandle, urls: Vec<Url>) -> bool {
+    for url in urls {
+        match parse_connect_link(&url) {
+            Some(server_name) => {
+                tracing::info!("Deep link to server: {}", server_name);
+                start_autoconnect(handle.clone(), server_name, AutoConnectOptions::default());
+                return true;
+            }
+            None => tracing::warn!("Ignoring unrecognised deep link: {}", url),
+        }
+    }
+    false
+}
+
+/// Bring the existing window forward when a second instance is started,
+/// e.g. by a link clicked while the launcher is open.
+pub fn focus_main_window(handle: &AppHandle) {
+    if let Some(window) = handle.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Listen for links, and handle the one the launcher was started with.
+/// Returns whether that link started a connection.
+pub fn init(handle: &AppHandle) -> bool {
+    let deep_link = handle.deep_link();
+
+    // Installers register the scheme, but AppImages and portable builds
+    // need it done at runtime
+    #[cfg(any(target_os = "linux", windows))]
+    if let Err(e) = deep_link.register_all() {
+        tracing::warn!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let handle_for_links = handle.clone();
+    deep_link.on_open_url(move |event| {
+        handle_urls(&handle_for_links, event.urls());
+    });
+
+    match deep_link.get_current() {
+        Ok(Some(urls)) => handle_urls(handle, urls),
+        Ok(None) => false,
+        Err(e) => {
+            tracing::warn!("Failed to read startup deep link: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(link: &str) -> Option<String> {
+        parse_connect_link(&Url::parse(link).unwrap())
+    }
+
+    #[test]
+    fn parses_server_name() {
+        assert_eq!(parse("cm-ss13://connect/Main"), Some("Main".to_string()));
+        assert_eq!(parse("cm-ss13://connect/Main/"), Some("Main".to_string()));
+        assert_eq!(parse("cm-ss13://connect//Main"), Some("Main".to_string()));
+    }
+
+    #[test]
+    fn decodes_percent_encoding() {
+        assert_eq!(
+            parse("cm-ss13://connect/Sorokyne%20Strata"),
+            Some("Sorokyne Strata".to_string())
+        );
+        assert_eq!(
+            parse("cm-ss13://connect/%20Main%20"),
+            Some("Main".to_string())
+        );
+        assert_eq!(parse("cm-ss13://connect/%FF"), None);
+    }
+
+    #[test]
+    fn leaves_plus_for_server_lookup() {
+        // Server lookup treats `+` as a space, as Steam launch options do
+        assert_eq!(
+            parse("cm-ss13://connect/Sorokyne+Strata"),
+            Some("Sorokyne+Strata".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        assert_eq!(parse("cm-ss13://join/Main"), None);
+        assert_eq!(parse("cm-ss13://Connect.example/Main"), None);
+        assert_eq!(parse("https://connect/Main"), None);
+        assert_eq!(parse("byond://connect/Main"), None);
+    }
+
+    #[test]
+    fn rejects_empty_server_names() {
+        assert_eq!(parse("cm-ss13://connect"), None);
+        assert_eq!(parse("cm-ss13://connect/"), None);
+        assert_eq!(parse("cm-ss13://connect//"), None);
+        assert_eq!(parse("cm-ss13://connect/%20"), None);
+    }
+}
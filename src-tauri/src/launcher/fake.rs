@@ -0,0 +1,124 @@
+//! A stand-in backend that runs a stub process instead of DreamSeeker
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::traits::{GameLauncher, LaunchRequest};
+
+/// Set to a command line such as `sleep 60` to launch that instead of the game.
+#[cfg(feature = "dev")]
+const FAKE_GAME_ENV: &str = "CM_LAUNCHER_FAKE_GAME";
+
+/// The stub receives the connect URL here rather than as an argument, so any
+/// command line can be used.
+const CONNECT_URL_ENV: &str = "CM_LAUNCHER_CONNECT_URL";
+
+/// Runs a stub command in place of DreamSeeker, so the connect flow and game
+/// session tracking can be exercised on machines without BYOND.
+pub struct FakeLauncher {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl FakeLauncher {
+    pub fn new(program: impl Into<PathBuf>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+        }
+    }
+
+    #[cfg(feature = "dev")]
+    pub fn from_env() -> Option<Self> {
+        let command_line = std::env::var(FAKE_GAME_ENV).ok()?;
+        let mut parts = command_line.split_whitespace().map(str::to_string);
+        let program = parts.next()?;
+
+        Some(Self::new(program, parts.collect()))
+    }
+}
+
+#[async_trait]
+impl GameLauncher for FakeLauncher {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn needs_install(&self) -> bool {
+        false
+    }
+
+    fn resolve_executable(&self, _install_dir: &Path) -> PathBuf {
+        self.program.clone()
+    }
+
+    async fn build_command(&self, request: &LaunchRequest) -> Result<Command, String> {
+        tracing::info!("Fake launcher standing in for DreamSeeker");
+
+        let mut command = Command::new(&request.executable);
+        command
+            .args(&self.args)
+            .env(CONNECT_URL_ENV, &request.connect_url);
+        Ok(command)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::launcher::launch;
+
+    const CONNECT_URL: &str = "byond://127.0.0.1:1400?access_token=secret";
+
+    fn shell(script: &str) -> FakeLauncher {
+        FakeLauncher::new("sh", vec!["-c".to_string(), script.to_string()])
+    }
+
+    fn request(launcher: &FakeLauncher) -> LaunchRequest {
+        LaunchRequest {
+            executable: launcher.resolve_executable(Path::new("/nonexistent/516.1667")),
+            connect_url: CONNECT_URL.to_string(),
+            webview2_data_dir: std::env::temp_dir(),
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_the_stub_instead_of_dreamseeker() {
+        let launcher = shell("exit 0");
+        let request = request(&launcher);
+        assert_eq!(request.executable, PathBuf::from("sh"));
+        assert!(!launcher.needs_install());
+
+        let child = launch(&launcher, &request).await.unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+    }
+
+    #[tokio::test]
+    async fn passes_the_connect_url_to_the_stub() {
+        let launcher = shell("printf %s \"$CM_LAUNCHER_CONNECT_URL\" >&2");
+        let child = launch(&launcher, &request(&launcher)).await.unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stderr), CONNECT_URL);
+    }
+
+    #[tokio::test]
+    async fn reports_a_crashing_stub() {
+        let launcher = shell("echo 'runtime error' >&2; exit 3");
+        let child = launch(&launcher, &request(&launcher)).await.unwrap();
+        assert!(launcher.identify_process(&child).starts_with("fake (pid "));
+
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(String::from_utf8_lossy(&output.stderr), "runtime error\n");
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_stub_is_missing() {
+        let launcher = FakeLauncher::new("/nonexistent/dreamseeker", Vec::new());
+        let err = launch(&launcher, &request(&launcher)).await.unwrap_err();
+        assert!(err.starts_with("Failed to launch DreamSeeker"), "{}", err);
+    }
+}
@@ -0,0 +1,15 @@
+#[cfg(any(feature = "dev", test))]
+mod fake;
+#[cfg(target_os = "windows")]
+mod native;
+mod traits;
+#[cfg(target_os = "linux")]
+mod wine;
+
+#[cfg(feature = "dev")]
+pub use fake::FakeLauncher;
+#[cfg(target_os = "windows")]
+pub use native::NativeLauncher;
+pub use traits::{dreamseeker_exe, launch, GameLauncher, LaunchRequest};
+#[cfg(target_os = "linux")]
+pub use wine::WineLauncher;
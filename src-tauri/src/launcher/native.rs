@@ -0,0 +1,28 @@
+//! Running DreamSeeker directly on Windows
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::traits::{dreamseeker_exe, GameLauncher, LaunchRequest};
+
+pub struct NativeLauncher;
+
+#[async_trait]
+impl GameLauncher for NativeLauncher {
+    fn name(&self) -> &'static str {
+        "native"
+    }
+
+    fn resolve_executable(&self, install_dir: &Path) -> PathBuf {
+        dreamseeker_exe(install_dir)
+    }
+
+    async fn build_command(&self, request: &LaunchRequest) -> Result<Command, String> {
+        let mut command = Command::new(&request.executable);
+        command
+            .arg(&request.connect_url)
+            .env("WEBVIEW2_USER_DATA_FOLDER", &request.webview2_data_dir);
+        Ok(command)
+    }
+}
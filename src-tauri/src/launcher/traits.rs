@@ -0,0 +1,63 @@
+//! The backends that start DreamSeeker
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/// Everything a backend needs to start DreamSeeker for one connection.
+#[derive(Debug, Clone)]
+pub struct LaunchRequest {
+    pub executable: PathBuf,
+    pub connect_url: String,
+    pub webview2_data_dir: PathBuf,
+}
+
+#[async_trait]
+pub trait GameLauncher: Send + Sync {
+    /// Returns the name of this backend (for logging)
+    fn name(&self) -> &'static str;
+
+    /// Resolve the executable to launch for a BYOND install directory
+    fn resolve_executable(&self, install_dir: &Path) -> PathBuf;
+
+    /// Whether the BYOND version has to be installed before launching
+    fn needs_install(&self) -> bool {
+        true
+    }
+
+    /// Build the command that starts the game, doing any setup it needs first
+    async fn build_command(&self, request: &LaunchRequest) -> Result<Command, String>;
+
+    /// Start the game process, with stderr piped so it can be kept for
+    /// crash reports
+    fn spawn(&self, mut command: Command) -> Result<Child, String> {
+        command
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to launch DreamSeeker: {}", e))
+    }
+
+    /// Describe the spawned process, for logs and diagnostics
+    fn identify_process(&self, child: &Child) -> String {
+        format!("{} (pid {})", self.name(), child.id())
+    }
+}
+
+/// Build and spawn the game for `request`.
+pub async fn launch(launcher: &dyn GameLauncher, request: &LaunchRequest) -> Result<Child, String> {
+    let command = launcher.build_command(request).await?;
+    let child = launcher.spawn(command)?;
+    tracing::info!(
+        "Launched DreamSeeker: {}",
+        launcher.identify_process(&child)
+    );
+    Ok(child)
+}
+
+/// Where DreamSeeker lives inside an extracted BYOND archive.
+pub fn dreamseeker_exe(install_dir: &Path) -> PathBuf {
+    install_dir
+        .join("byond")
+        .join("bin")
+        .join("dreamseeker.exe")
+}
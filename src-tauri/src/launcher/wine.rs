@@ -0,0 +1,59 @@
+//! Running the Windows DreamSeeker build through Wine or Proton
+
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::traits::{dreamseeker_exe, GameLauncher, LaunchRequest};
+use crate::byond::{ensure_prefix, runner_command};
+use crate::settings::WineSettings;
+
+/// Translate a host path into the path Wine exposes it as on its `Z:` drive,
+/// which maps to the host root.
+pub fn to_wine_path(path: &Path) -> String {
+    format!("Z:{}", path.to_string_lossy().replace('/', "\\"))
+}
+
+pub struct WineLauncher {
+    settings: WineSettings,
+    prefix_dir: PathBuf,
+}
+
+impl WineLauncher {
+    pub fn new(settings: WineSettings, prefix_dir: PathBuf) -> Self {
+        Self {
+            settings,
+            prefix_dir,
+        }
+    }
+}
+
+#[async_trait]
+impl GameLauncher for WineLauncher {
+    fn name(&self) -> &'static str {
+        "wine"
+    }
+
+    fn resolve_executable(&self, install_dir: &Path) -> PathBuf {
+        dreamseeker_exe(install_dir)
+    }
+
+    async fn build_command(&self, request: &LaunchRequest) -> Result<Command, String> {
+        let settings = self.settings.clone();
+        let prefix_dir = self.prefix_dir.clone();
+        tokio::task::spawn_blocking(move || ensure_prefix(&settings, &prefix_dir))
+            .await
+            .map_err(|e| format!("Wine prefix task failed: {}", e))??;
+
+        let mut command = runner_command(&self.settings, &self.prefix_dir)?;
+        command
+            .arg(&request.executable)
+            .arg(&request.connect_url)
+            .env(
+                "WEBVIEW2_USER_DATA_FOLDER",
+                to_wine_path(&request.webview2_data_dir),
+            );
+
+        Ok(command)
+    }
+}
@@ -1,10 +1,15 @@
 mod auth;
 mod autoconnect;
 mod byond;
+mod cli;
+mod connection;
 mod control_server;
+mod deep_link;
 mod discord;
+mod launcher;
 mod logging;
 mod presence;
+mod reconnect;
 mod relays;
 mod servers;
 mod settings;
@@ -22,12 +27,22 @@ use auth::{
     background_refresh_task, get_access_token, get_auth_state, logout, refresh_auth, start_login,
 };
 use byond::{
-    check_byond_version, connect_to_server, connect_to_url, delete_byond_version,
-    install_byond_version, is_byond_pager_running, is_dev_mode, list_installed_byond_versions,
+    cancel_byond_prefetch, check_byond_version, connect_to_custom_server, connect_to_server,
+    connect_to_url, delete_byond_version, delete_wine_prefix, get_latest_byond_versions,
+    get_wine_prefix_status, import_byond_version, install_byond_version, is_byond_pager_running,
+    is_dev_mode, list_installed_byond_versions, prune_byond_versions, rebuild_wine_prefix,
 };
+use connection::{cancel_connection, get_connection_state};
+use presence::{get_playtime, get_recent_sessions};
+use reconnect::cancel_reconnect;
 use relays::{get_relays, get_selected_relay, set_selected_relay};
 use servers::get_servers;
-use settings::{get_settings, set_auth_mode, set_theme};
+use settings::{
+    add_custom_server, get_settings, list_custom_servers, remove_custom_server,
+    set_allow_unverified_byond, set_auth_mode, set_auto_reconnect, set_byond_retention,
+    set_byond_version_override, set_prefetch_byond_versions, set_server_favourite,
+    set_server_order, set_theme, set_wine_settings,
+};
 
 #[cfg(feature = "steam")]
 use steam::{
@@ -55,6 +70,7 @@ fn kill_game(
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let _guard = logging::init_logging();
+    let cli_args = cli::CliArgs::from_env();
 
     #[cfg(target_os = "windows")]
     {
@@ -66,7 +82,24 @@ pub fn run() {
     }
 
     #[allow(unused_mut)]
-    let mut builder = tauri::Builder::default().plugin(tauri_plugin_opener::init());
+    let mut builder = tauri::Builder::default();
+
+    // Must be registered first, so a second launch (e.g. from a deep link)
+    // hands over to this instance before anything else starts
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            let args = cli::CliArgs::parse(args.into_iter().skip(1));
+            if !args.minimized {
+                deep_link::focus_main_window(app);
+            }
+            args.start_autoconnect(app);
+        }));
+    }
+
+    builder = builder
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_opener::init());
 
     #[cfg(not(feature = "steam"))]
     {
@@ -74,11 +107,22 @@ pub fn run() {
             greet,
             check_byond_version,
             install_byond_version,
+            import_byond_version,
             connect_to_server,
             connect_to_url,
+            connect_to_custom_server,
+            get_connection_state,
+            cancel_connection,
+            cancel_reconnect,
             is_dev_mode,
             list_installed_byond_versions,
             delete_byond_version,
+            prune_byond_versions,
+            get_latest_byond_versions,
+            cancel_byond_prefetch,
+            get_wine_prefix_status,
+            rebuild_wine_prefix,
+            delete_wine_prefix,
             is_byond_pager_running,
             start_login,
             logout,
@@ -88,8 +132,21 @@ pub fn run() {
             get_settings,
             set_auth_mode,
             set_theme,
+            set_wine_settings,
+            set_byond_retention,
+            set_prefetch_byond_versions,
+            set_allow_unverified_byond,
+            set_byond_version_override,
+            set_auto_reconnect,
+            list_custom_servers,
+            add_custom_server,
+            remove_custom_server,
+            set_server_favourite,
+            set_server_order,
             get_control_server_port,
             kill_game,
+            get_recent_sessions,
+            get_playtime,
             get_servers,
             get_relays,
             get_selected_relay,
@@ -103,11 +160,22 @@ pub fn run() {
             greet,
             check_byond_version,
             install_byond_version,
+            import_byond_version,
             connect_to_server,
             connect_to_url,
+            connect_to_custom_server,
+            get_connection_state,
+            cancel_connection,
+            cancel_reconnect,
             is_dev_mode,
             list_installed_byond_versions,
             delete_byond_version,
+            prune_byond_versions,
+            get_latest_byond_versions,
+            cancel_byond_prefetch,
+            get_wine_prefix_status,
+            rebuild_wine_prefix,
+            delete_wine_prefix,
             is_byond_pager_running,
             start_login,
             logout,
@@ -117,8 +185,21 @@ pub fn run() {
             get_settings,
             set_auth_mode,
             set_theme,
+            set_wine_settings,
+            set_byond_retention,
+            set_prefetch_byond_versions,
+            set_allow_unverified_byond,
+            set_byond_version_override,
+            set_auto_reconnect,
+            list_custom_servers,
+            add_custom_server,
+            remove_custom_server,
+            set_server_favourite,
+            set_server_order,
             get_control_server_port,
             kill_game,
+            get_recent_sessions,
+            get_playtime,
             get_servers,
             get_relays,
             get_selected_relay,
@@ -197,16 +278,28 @@ pub fn run() {
     let presence_manager = std::sync::Arc::new(manager);
     let server_state = std::sync::Arc::new(servers::ServerState::new());
     let relay_state = std::sync::Arc::new(relays::RelayState::new());
+    let install_coordinator = std::sync::Arc::new(byond::InstallCoordinator::new());
+    let prefetcher = std::sync::Arc::new(byond::Prefetcher::new());
+    let connection_state = std::sync::Arc::new(connection::ConnectionStateMachine::new());
+    let release_cache = std::sync::Arc::new(byond::ReleaseCache::from_env());
+    let reconnector = std::sync::Arc::new(reconnect::Reconnector::new());
 
     builder = builder
         .manage(std::sync::Arc::clone(&presence_manager))
         .manage(std::sync::Arc::clone(&server_state))
-        .manage(std::sync::Arc::clone(&relay_state));
+        .manage(std::sync::Arc::clone(&relay_state))
+        .manage(install_coordinator)
+        .manage(prefetcher)
+        .manage(connection_state)
+        .manage(release_cache)
+        .manage(reconnector);
 
     builder
         .setup(move |app| {
             let handle = app.handle().clone();
 
+            byond::cleanup_stale_installs(&handle);
+
             presence::start_presence_background_task(
                 std::sync::Arc::clone(&presence_manager),
                 steam_poll_callback,
@@ -257,7 +350,17 @@ pub fn run() {
                 relays::init_relays(&relay_state_init, &handle_for_relay_init).await;
             });
 
-            autoconnect::check_and_start_autoconnect(handle);
+            if cli_args.minimized {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.minimize();
+                }
+            }
+
+            // Only one startup connection, so they don't race each other
+            let connecting = deep_link::init(&handle) || cli_args.start_autoconnect(&handle);
+            if !connecting {
+                autoconnect::check_and_start_autoconnect(handle);
+            }
 
             Ok(())
         })
@@ -0,0 +1,196 @@
+//! Recording how and why a game process ended
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::process::{ChildStderr, ExitStatus};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How much of the end of DreamSeeker's stderr to keep for crash reports.
+const STDERR_TAIL_BYTES: usize = 64 * 1024;
+/// How long to let the reader drain what's left in the pipe after the game
+/// exits. Processes the game started can hold the pipe open indefinitely.
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitKind {
+    /// The player closed the game.
+    Normal,
+    /// The launcher killed the game, e.g. to restart it.
+    Killed,
+    /// The game exited with an error code, or was killed by something else.
+    Crashed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExit {
+    pub server_name: Option<String>,
+    pub kind: ExitKind,
+    pub exit_code: Option<i32>,
+    pub duration_secs: u64,
+    pub stderr: String,
+}
+
+/// Drains a child's stderr on its own thread, keeping the last
+/// `STDERR_TAIL_BYTES`. The pipe must be read continuously or the game
+/// blocks once the buffer fills.
+#[derive(Clone, Default)]
+pub struct StderrCapture {
+    tail: Arc<Mutex<Vec<u8>>>,
+    reader: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl StderrCapture {
+    pub fn spawn(mut stderr: ChildStderr) -> Self {
+        let capture = Self::default();
+        let tail = Arc::clone(&capture.tail);
+
+        let reader = thread::spawn(move || {
+            let mut buffer = [0u8; 4096];
+            loop {
+                match stderr.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        let mut tail = tail.lock().unwrap();
+                        tail.extend_from_slice(&buffer[..n]);
+                        if tail.len() > STDERR_TAIL_BYTES {
+                            let excess = tail.len() - STDERR_TAIL_BYTES;
+                            tail.drain(..excess);
+                        }
+                    }
+                }
+            }
+        });
+        *capture.reader.lock().unwrap() = Some(reader);
+
+        capture
+    }
+
+    /// Wait up to `timeout` for the reader to reach the end of the pipe, so
+    /// the last lines written before an exit aren't missed.
+    pub fn wait_for_reader(&self, timeout: Duration) {
+        let Some(reader) = self.reader.lock().unwrap().take() else {
+            return;
+        };
+
+        let deadline = Instant::now() + timeout;
+        while !reader.is_finished() {
+            if Instant::now() >= deadline {
+                tracing::debug!("Game stderr still open after exit, using what was read");
+                *self.reader.lock().unwrap() = Some(reader);
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let _ = reader.join();
+    }
+
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.tail.lock().unwrap()).into_owned()
+    }
+}
+
+/// A running game process and what's needed to report on its exit.
+pub struct GameProcess {
+    pub child: std::process::Child,
+    pub started: Instant,
+    pub stderr: StderrCapture,
+}
+
+impl GameProcess {
+    pub fn new(mut child: std::process::Child) -> Self {
+        let stderr = child
+            .stderr
+            .take()
+            .map(StderrCapture::spawn)
+            .unwrap_or_default();
+
+        Self {
+            child,
+            started: Instant::now(),
+            stderr,
+        }
+    }
+
+    /// Describe the exit. `status` is `None` when it couldn't be read.
+    pub fn exit(
+        &self,
+        server_name: Option<String>,
+        status: Option<ExitStatus>,
+        killed: bool,
+    ) -> GameExit {
+        let exit_code = status.and_then(|s| s.code());
+        let kind = if killed {
+            ExitKind::Killed
+        } else if status.is_some_and(|s| s.success()) {
+            ExitKind::Normal
+        } else {
+            ExitKind::Crashed
+        };
+
+        self.stderr.wait_for_reader(STDERR_DRAIN_TIMEOUT);
+
+        GameExit {
+            server_name,
+            kind,
+            exit_code,
+            duration_secs: self.started.elapsed().as_secs(),
+            stderr: self.stderr.contents(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    fn run(script: &str) -> GameProcess {
+        let child = Command::new("sh")
+            .args(["-c", script])
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        GameProcess::new(child)
+    }
+
+    #[test]
+    fn keeps_the_last_lines_of_a_crash() {
+        let mut game = run("i=0; while [ $i -lt 2000 ]; do echo line $i >&2; i=$((i+1)); done; echo fatal >&2; exit 2");
+        let status = game.child.wait().ok();
+
+        let exit = game.exit(Some("Test".to_string()), status, false);
+        assert_eq!(exit.kind, ExitKind::Crashed);
+        assert_eq!(exit.exit_code, Some(2));
+        assert!(
+            exit.stderr.ends_with("line 1999\nfatal\n"),
+            "{}",
+            exit.stderr
+        );
+    }
+
+    #[test]
+    fn does_not_wait_forever_for_a_held_pipe() {
+        // The background sleep inherits stderr and keeps the pipe open
+        let mut game = run("echo done >&2; sleep 5 & exit 0");
+        let status = game.child.wait().ok();
+
+        let started = Instant::now();
+        let exit = game.exit(None, status, false);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(exit.kind, ExitKind::Normal);
+        assert_eq!(exit.stderr, "done\n");
+    }
+
+    #[test]
+    fn reports_killed_games() {
+        let mut game = run("sleep 5");
+        game.child.kill().unwrap();
+        let status = game.child.wait().ok();
+
+        assert_eq!(game.exit(None, status, true).kind, ExitKind::Killed);
+    }
+}
@@ -0,0 +1,272 @@
+//! Persistent record of past game sessions and playtime per server
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::exit::{ExitKind, GameExit};
+use super::traits::ConnectionParams;
+use crate::byond::ByondVersion;
+
+const HISTORY_FILE: &str = "session_history.json";
+/// How many individual sessions to keep. Playtime totals are kept for every
+/// session regardless.
+const MAX_SESSIONS: usize = 200;
+const DEFAULT_RECENT_LIMIT: usize = 20;
+
+/// A session that has started but not ended yet.
+#[derive(Debug, Clone)]
+pub struct ActiveSession {
+    pub server_name: String,
+    /// The relay or server address connected to.
+    pub host: String,
+    pub byond_version: ByondVersion,
+    pub auth_mode: Option<String>,
+    pub started_at: DateTime<Utc>,
+}
+
+impl ActiveSession {
+    pub fn start(params: &ConnectionParams) -> Self {
+        Self {
+            server_name: params.server_name.clone(),
+            host: params.host.clone(),
+            byond_version: params.version,
+            auth_mode: params.access_type.clone(),
+            started_at: Utc::now(),
+        }
+    }
+
+    /// Close the session. `exit` is `None` when the process was dropped
+    /// without its exit being observed.
+    pub fn end(self, exit: Option<&GameExit>) -> SessionRecord {
+        SessionRecord {
+            server_name: self.server_name,
+            host: self.host,
+            byond_version: self.byond_version,
+            auth_mode: self.auth_mode,
+            started_at: self.started_at,
+            ended_at: Utc::now(),
+            exit_kind: exit.map(|e| e.kind),
+            exit_code: exit.and_then(|e| e.exit_code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub server_name: String,
+    #[serde(alias = "relay")]
+    pub host: String,
+    pub byond_version: ByondVersion,
+    pub auth_mode: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub exit_kind: Option<ExitKind>,
+    pub exit_code: Option<i32>,
+}
+
+impl SessionRecord {
+    pub fn duration_secs(&self) -> u64 {
+        (self.ended_at - self.started_at).num_seconds().max(0) as u64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerPlaytime {
+    pub server_name: String,
+    pub total_secs: u64,
+    pub sessions: u32,
+    pub last_played: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    /// Oldest first.
+    #[serde(default)]
+    sessions: Vec<SessionRecord>,
+    #[serde(default)]
+    playtime: HashMap<String, ServerPlaytime>,
+}
+
+pub struct SessionHistory;
+
+impl SessionHistory {
+    fn get_history_file_path() -> Result<PathBuf, String> {
+        let data_dir = dirs::data_local_dir()
+            .ok_or("Failed to get local data directory")?
+            .join("com.cm-ss13.launcher");
+
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+        Ok(data_dir.join(HISTORY_FILE))
+    }
+
+    /// Read the history file. One that can't be parsed is moved aside rather
+    /// than overwritten, so the playtime in it isn't lost.
+    fn load(path: &Path) -> Result<HistoryFile, String> {
+        if !path.exists() {
+            return Ok(HistoryFile::default());
+        }
+
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read session history: {}", e))?;
+
+        match serde_json::from_str(&contents) {
+            Ok(history) => Ok(history),
+            Err(e) => {
+                let backup = path.with_extension(format!(
+                    "json.corrupt-{}",
+                    Utc::now().format("%Y%m%d%H%M%S")
+                ));
+                fs::rename(path, &backup).map_err(|e| {
+                    format!("Failed to move aside unreadable session history: {}", e)
+                })?;
+                tracing::warn!(
+                    "Failed to parse session history, moved it to {} and starting over: {}",
+                    backup.display(),
+                    e
+                );
+                Ok(HistoryFile::default())
+            }
+        }
+    }
+
+    fn save(path: &Path, history: &HistoryFile) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(history)
+            .map_err(|e| format!("Failed to serialize session history: {}", e))?;
+
+        fs::write(path, contents).map_err(|e| format!("Failed to write session history: {}", e))
+    }
+
+    pub fn record(session: SessionRecord) -> Result<(), String> {
+        Self::record_to(&Self::get_history_file_path()?, session)
+    }
+
+    fn record_to(path: &Path, session: SessionRecord) -> Result<(), String> {
+        let mut history = Self::load(path)?;
+
+        let duration = session.duration_secs();
+        history
+            .playtime
+            .entry(session.server_name.clone())
+            .and_modify(|playtime| {
+                playtime.total_secs += duration;
+                playtime.sessions += 1;
+                playtime.last_played = playtime.last_played.max(session.ended_at);
+            })
+            .or_insert_with(|| ServerPlaytime {
+                server_name: session.server_name.clone(),
+                total_secs: duration,
+                sessions: 1,
+                last_played: session.ended_at,
+            });
+
+        history.sessions.push(session);
+        if history.sessions.len() > MAX_SESSIONS {
+            let excess = history.sessions.len() - MAX_SESSIONS;
+            history.sessions.drain(..excess);
+        }
+
+        Self::save(path, &history)
+    }
+
+    /// Sessions newest first.
+    pub fn recent(limit: usize) -> Result<Vec<SessionRecord>, String> {
+        let history = Self::load(&Self::get_history_file_path()?)?;
+        Ok(history.sessions.into_iter().rev().take(limit).collect())
+    }
+
+    /// Playtime per server, most played first.
+    pub fn playtime() -> Result<Vec<ServerPlaytime>, String> {
+        let history = Self::load(&Self::get_history_file_path()?)?;
+        let mut playtime: Vec<_> = history.playtime.into_values().collect();
+        playtime.sort_by_key(|p| std::cmp::Reverse(p.total_secs));
+        Ok(playtime)
+    }
+}
+
+#[tauri::command]
+pub async fn get_recent_sessions(limit: Option<usize>) -> Result<Vec<SessionRecord>, String> {
+    SessionHistory::recent(limit.unwrap_or(DEFAULT_RECENT_LIMIT))
+}
+
+#[tauri::command]
+pub async fn get_playtime() -> Result<Vec<ServerPlaytime>, String> {
+    SessionHistory::playtime()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("history-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn session(server_name: &str, secs: i64) -> SessionRecord {
+        let ended_at = Utc::now();
+        SessionRecord {
+            server_name: server_name.to_string(),
+            host: "direct.cm-ss13.com".to_string(),
+            byond_version: "516.1667".parse().unwrap(),
+            auth_mode: Some("cm_ss13".to_string()),
+            started_at: ended_at - chrono::Duration::seconds(secs),
+            ended_at,
+            exit_kind: Some(ExitKind::Normal),
+            exit_code: Some(0),
+        }
+    }
+
+    #[test]
+    fn totals_playtime_per_server() {
+        let dir = temp_dir();
+        let path = dir.join(HISTORY_FILE);
+
+        SessionHistory::record_to(&path, session("Main", 60)).unwrap();
+        SessionHistory::record_to(&path, session("Main", 30)).unwrap();
+        SessionHistory::record_to(&path, session("Other", 10)).unwrap();
+
+        let history = SessionHistory::load(&path).unwrap();
+        assert_eq!(history.sessions.len(), 3);
+        assert_eq!(history.playtime["Main"].total_secs, 90);
+        assert_eq!(history.playtime["Main"].sessions, 2);
+        assert_eq!(history.playtime["Other"].total_secs, 10);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn keeps_an_unreadable_file() {
+        let dir = temp_dir();
+        let path = dir.join(HISTORY_FILE);
+        fs::write(&path, "{ not json").unwrap();
+
+        SessionHistory::record_to(&path, session("Main", 60)).unwrap();
+
+        let backups: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|p| p != &path)
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "{ not json");
+        assert_eq!(SessionHistory::load(&path).unwrap().sessions.len(), 1);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn reads_records_saved_with_relay() {
+        let json = r#"{"server_name": "Main", "relay": "direct.cm-ss13.com",
+            "byond_version": "516.1667", "auth_mode": null,
+            "started_at": "2026-01-01T00:00:00Z", "ended_at": "2026-01-01T01:00:00Z",
+            "exit_kind": "normal", "exit_code": 0}"#;
+
+        let record: SessionRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.host, "direct.cm-ss13.com");
+        assert_eq!(record.duration_secs(), 3600);
+    }
+}
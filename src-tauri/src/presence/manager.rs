@@ -4,8 +4,12 @@ use std::process::Child;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use super::exit::{GameExit, GameProcess};
+use super::history::{ActiveSession, SessionHistory};
 use super::status::fetch_player_count;
 use super::traits::{ConnectionParams, GameSession, PresenceProvider, PresenceState};
+use crate::connection::ConnectionStateMachine;
+use crate::reconnect::Reconnector;
 
 const STATUS_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
 
@@ -13,8 +17,10 @@ const STATUS_UPDATE_INTERVAL: Duration = Duration::from_secs(30);
 pub struct PresenceManager {
     providers: Vec<Box<dyn PresenceProvider>>,
     game_session: Arc<Mutex<Option<GameSession>>>,
-    game_process: Arc<Mutex<Option<Child>>>,
+    game_process: Arc<Mutex<Option<GameProcess>>>,
     last_connection_params: Arc<Mutex<Option<ConnectionParams>>>,
+    last_exit: Arc<Mutex<Option<GameExit>>>,
+    active_session: Arc<Mutex<Option<ActiveSession>>>,
 }
 
 impl PresenceManager {
@@ -24,6 +30,8 @@ impl PresenceManager {
             game_session: Arc::new(Mutex::new(None)),
             game_process: Arc::new(Mutex::new(None)),
             last_connection_params: Arc::new(Mutex::new(None)),
+            last_exit: Arc::new(Mutex::new(None)),
+            active_session: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -34,7 +42,6 @@ impl PresenceManager {
         self.providers.push(provider);
     }
 
-    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
     pub fn start_game_session(&self, server_name: String, status_url: String, process: Child) {
         tracing::info!("Starting game session on {}", server_name);
         {
@@ -46,7 +53,12 @@ impl PresenceManager {
         }
         {
             let mut proc = self.game_process.lock().unwrap();
-            *proc = Some(process);
+            *proc = Some(GameProcess::new(process));
+        }
+
+        self.end_session_record();
+        if let Some(params) = self.get_last_connection_params() {
+            *self.active_session.lock().unwrap() = Some(ActiveSession::start(&params));
         }
 
         self.update_all_presence(&PresenceState::Playing {
@@ -58,35 +70,73 @@ impl PresenceManager {
     pub fn check_game_running(&self) -> bool {
         let mut proc_guard = self.game_process.lock().unwrap();
 
-        if let Some(ref mut child) = *proc_guard {
-            match child.try_wait() {
-                Ok(Some(_status)) => {
+        if let Some(ref mut game) = *proc_guard {
+            let status = match game.child.try_wait() {
+                Ok(Some(status)) => {
                     // Process has exited
-                    drop(proc_guard);
-                    self.clear_game_session();
-                    false
+                    Some(status)
                 }
                 Ok(None) => {
                     // Process still running
-                    true
+                    return true;
                 }
-                Err(_) => {
+                Err(e) => {
                     // Error checking process, assume dead
-                    drop(proc_guard);
-                    self.clear_game_session();
-                    false
+                    tracing::warn!("Failed to check game process: {}", e);
+                    None
                 }
-            }
+            };
+
+            let exit = game.exit(self.session_server_name(), status, false);
+            drop(proc_guard);
+            self.record_exit(exit);
+            self.clear_game_session();
+            false
         } else {
             false
         }
     }
 
+    fn session_server_name(&self) -> Option<String> {
+        self.get_game_session().map(|session| session.server_name)
+    }
+
+    fn record_exit(&self, exit: GameExit) {
+        tracing::info!(
+            "Game exited: {:?}, code {:?}, after {}s",
+            exit.kind,
+            exit.exit_code,
+            exit.duration_secs
+        );
+        if !exit.stderr.is_empty() {
+            tracing::debug!("Game stderr:\n{}", exit.stderr);
+        }
+        *self.last_exit.lock().unwrap() = Some(exit);
+    }
+
+    /// The most recent game exit, if it hasn't been taken yet.
+    pub fn take_last_exit(&self) -> Option<GameExit> {
+        self.last_exit.lock().unwrap().take()
+    }
+
     pub fn get_game_session(&self) -> Option<GameSession> {
         self.game_session.lock().unwrap().clone()
     }
 
+    /// Write the active session, if any, to the session history.
+    fn end_session_record(&self) {
+        let Some(session) = self.active_session.lock().unwrap().take() else {
+            return;
+        };
+
+        let record = session.end(self.last_exit.lock().unwrap().as_ref());
+        if let Err(e) = SessionHistory::record(record) {
+            tracing::warn!("Failed to record session history: {}", e);
+        }
+    }
+
     pub fn clear_game_session(&self) {
+        self.end_session_record();
         {
             let mut session = self.game_session.lock().unwrap();
             *session = None;
@@ -98,7 +148,6 @@ impl PresenceManager {
         self.update_all_presence(&PresenceState::InLauncher);
     }
 
-    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
     pub fn set_last_connection_params(&self, params: ConnectionParams) {
         let mut connection_params = self.last_connection_params.lock().unwrap();
         *connection_params = Some(params);
@@ -111,13 +160,15 @@ impl PresenceManager {
     pub fn kill_game_process(&self) -> bool {
         let mut proc_guard = self.game_process.lock().unwrap();
 
-        if let Some(ref mut child) = *proc_guard {
-            match child.kill() {
+        if let Some(ref mut game) = *proc_guard {
+            match game.child.kill() {
                 Ok(()) => {
                     tracing::info!("Game process killed successfully");
 
-                    let _ = child.wait();
+                    let status = game.child.wait().ok();
+                    let exit = game.exit(self.session_server_name(), status, true);
                     drop(proc_guard);
+                    self.record_exit(exit);
                     self.clear_game_session();
                     true
                 }
@@ -158,7 +209,7 @@ pub fn start_presence_background_task(
     poll_callback: Option<Box<dyn Fn() + Send + Sync>>,
     app_handle: tauri::AppHandle,
 ) {
-    use tauri::Emitter;
+    use tauri::{Emitter, Manager};
 
     tauri::async_runtime::spawn(async move {
         let poll_interval = Duration::from_millis(100);
@@ -198,7 +249,15 @@ pub fn start_presence_background_task(
                 was_game_running = false;
                 last_player_count = None;
                 presence_manager.update_all_presence(&PresenceState::InLauncher);
-                app_handle.emit("game-closed", ()).ok();
+                if let Some(machine) = app_handle.try_state::<Arc<ConnectionStateMachine>>() {
+                    machine.game_exited(&app_handle);
+                }
+                if let Some(exit) = presence_manager.take_last_exit() {
+                    if let Some(reconnector) = app_handle.try_state::<Arc<Reconnector>>() {
+                        reconnector.game_exited(&app_handle, &exit);
+                    }
+                    app_handle.emit("game-exited", exit).ok();
+                }
             }
 
             tokio::time::sleep(poll_interval).await;
@@ -1,7 +1,12 @@
+mod exit;
+mod history;
 mod manager;
 mod status;
 mod traits;
 
+#[allow(unused_imports)]
+pub use exit::{ExitKind, GameExit};
+pub use history::{get_playtime, get_recent_sessions};
 pub use manager::{start_presence_background_task, PresenceManager};
 #[allow(unused_imports)]
 pub use traits::{ConnectionParams, GameSession, PresenceProvider, PresenceState};
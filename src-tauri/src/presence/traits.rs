@@ -1,3 +1,5 @@
+use crate::byond::ByondVersion;
+
 #[derive(Debug, Clone)]
 pub struct GameSession {
     pub server_name: String,
@@ -6,7 +8,7 @@ pub struct GameSession {
 
 #[derive(Debug, Clone)]
 pub struct ConnectionParams {
-    pub version: String,
+    pub version: ByondVersion,
     pub host: String,
     pub port: String,
     pub access_type: Option<String>,
@@ -0,0 +1,334 @@
+//! Reconnecting to the last server after the game crashes
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::connection::{ConnectionState, ConnectionStateMachine};
+use crate::control_server::refresh_auth_token;
+use crate::presence::{ExitKind, GameExit, PresenceManager};
+use crate::settings::{load_settings, AutoReconnect};
+
+const STATUS_EVENT: &str = "reconnect-status";
+/// A crash after a session at least this long starts a fresh run of
+/// attempts; anything shorter counts against the current one.
+const STABLE_SESSION: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ReconnectStatus {
+    Waiting {
+        attempt: u32,
+        max_attempts: u32,
+        delay_secs: u64,
+    },
+    Connecting {
+        attempt: u32,
+        max_attempts: u32,
+    },
+    Reconnected,
+    GaveUp {
+        error: String,
+    },
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconnectEvent {
+    pub server_name: String,
+    #[serde(flatten)]
+    pub status: ReconnectStatus,
+}
+
+fn emit_status(app: &AppHandle, server_name: &str, status: ReconnectStatus) {
+    let event = ReconnectEvent {
+        server_name: server_name.to_string(),
+        status,
+    };
+    app.emit(STATUS_EVENT, &event).ok();
+}
+
+/// How long to wait before the given attempt, doubling from the initial
+/// delay up to the maximum.
+fn backoff_delay(policy: &AutoReconnect, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    let secs = policy
+        .initial_delay_secs
+        .saturating_mul(factor)
+        .min(policy.max_delay_secs);
+    Duration::from_secs(secs)
+}
+
+#[derive(Default)]
+struct Inner {
+    task: Option<JoinHandle<()>>,
+    server_name: Option<String>,
+    /// Attempts made since the game last ran for `STABLE_SESSION`.
+    attempts: u32,
+    /// Whether the task is inside a connection attempt of its own, which is
+    /// the only time cancelling may touch the connection state.
+    connecting: bool,
+    cancelled: bool,
+}
+
+/// Owns the reconnect task. Only one runs at a time.
+#[derive(Default)]
+pub struct Reconnector {
+    inner: Mutex<Inner>,
+}
+
+impl Reconnector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start reconnecting if `exit` was a crash and the player has opted in.
+    pub fn game_exited(self: &Arc<Self>, app: &AppHandle, exit: &GameExit) {
+        let mut inner = self.inner.lock().unwrap();
+        if exit.duration_secs >= STABLE_SESSION.as_secs() {
+            inner.attempts = 0;
+        }
+
+        if exit.kind != ExitKind::Crashed {
+            return;
+        }
+
+        let policy = match load_settings(app) {
+            Ok(settings) if settings.auto_reconnect.enabled => settings.auto_reconnect,
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!("Not reconnecting, failed to load settings: {}", e);
+                return;
+            }
+        };
+
+        let Some(server_name) = exit.server_name.clone() else {
+            return;
+        };
+
+        if inner
+            .task
+            .as_ref()
+            .is_some_and(|t| !t.inner().is_finished())
+        {
+            return;
+        }
+
+        if inner.attempts >= policy.max_attempts {
+            tracing::warn!(
+                "Game crashed again, giving up after {} reconnect attempts",
+                inner.attempts
+            );
+            inner.attempts = 0;
+            emit_status(
+                app,
+                &server_name,
+                ReconnectStatus::GaveUp {
+                    error: "The game keeps crashing".to_string(),
+                },
+            );
+            return;
+        }
+
+        tracing::info!("Game crashed, reconnecting to {}", server_name);
+        inner.server_name = Some(server_name.clone());
+        inner.connecting = false;
+        inner.cancelled = false;
+        inner.task = Some(tauri::async_runtime::spawn(reconnect(
+            Arc::clone(self),
+            app.clone(),
+            server_name,
+            policy,
+        )));
+    }
+
+    /// Stop reconnecting. A connection the reconnect already started is
+    /// cancelled through the connection state, so it winds down cleanly; one
+    /// the player started is left alone.
+    pub fn cancel(&self, app: &AppHandle) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let Some(task) = inner.task.take() else {
+            return false;
+        };
+        if task.inner().is_finished() {
+            return false;
+        }
+
+        tracing::info!("Cancelling reconnect");
+        inner.attempts = 0;
+        inner.cancelled = true;
+
+        if inner.connecting {
+            if let Some(machine) = app.try_state::<Arc<ConnectionStateMachine>>() {
+                machine.cancel();
+            }
+            // The task reports the cancellation once its attempt returns
+            inner.task = Some(task);
+            return true;
+        }
+
+        task.abort();
+        if let Some(server_name) = inner.server_name.take() {
+            emit_status(app, &server_name, ReconnectStatus::Cancelled);
+        }
+        true
+    }
+
+    /// Mark the task as inside its own connection attempt. Returns `false`
+    /// if the reconnect was cancelled first.
+    fn start_connecting(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.cancelled {
+            return false;
+        }
+        inner.connecting = true;
+        true
+    }
+
+    /// Mark the connection attempt as over. Returns whether the reconnect
+    /// was cancelled during it.
+    fn finish_connecting(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.connecting = false;
+        inner.cancelled
+    }
+
+    /// Count an attempt, returning its number or `None` once they're used up.
+    fn next_attempt(&self, policy: &AutoReconnect) -> Option<u32> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.attempts >= policy.max_attempts {
+            return None;
+        }
+        inner.attempts += 1;
+        Some(inner.attempts)
+    }
+
+    fn reset(&self) {
+        self.inner.lock().unwrap().attempts = 0;
+    }
+}
+
+async fn reconnect(
+    reconnector: Arc<Reconnector>,
+    app: AppHandle,
+    server_name: String,
+    policy: AutoReconnect,
+) {
+    let Some(presence_manager) = app.try_state::<Arc<PresenceManager>>() else {
+        return;
+    };
+    let max_attempts = policy.max_attempts;
+    let mut last_error = "No reconnect attempts allowed".to_string();
+
+    while let Some(attempt) = reconnector.next_attempt(&policy) {
+        let delay = backoff_delay(&policy, attempt);
+        emit_status(
+            &app,
+            &server_name,
+            ReconnectStatus::Waiting {
+                attempt,
+                max_attempts,
+                delay_secs: delay.as_secs(),
+            },
+        );
+        tokio::time::sleep(delay).await;
+
+        if presence_manager.check_game_running() {
+            tracing::info!("Game already running, no need to reconnect");
+            return;
+        }
+
+        let params = match presence_manager.get_last_connection_params() {
+            Some(params) if params.server_name == server_name => params,
+            _ => {
+                last_error = "No previous connection to reconnect to".to_string();
+                break;
+            }
+        };
+
+        emit_status(
+            &app,
+            &server_name,
+            ReconnectStatus::Connecting {
+                attempt,
+                max_attempts,
+            },
+        );
+
+        let params = match refresh_auth_token(&app, params).await {
+            Ok(params) => params,
+            Err(e) => {
+                tracing::warn!(
+                    "Reconnect attempt {} failed to refresh auth: {}",
+                    attempt,
+                    e
+                );
+                last_error = e;
+                continue;
+            }
+        };
+
+        if !reconnector.start_connecting() {
+            return;
+        }
+
+        let result = crate::byond::connect_to_server_internal(
+            app.clone(),
+            params.version,
+            params.host,
+            params.port,
+            params.access_type,
+            params.access_token,
+            params.server_name,
+            Some("auto_reconnect".to_string()),
+        )
+        .await;
+
+        let launched = matches!(&result, Ok(result) if result.success);
+        if reconnector.finish_connecting() && !launched {
+            emit_status(&app, &server_name, ReconnectStatus::Cancelled);
+            return;
+        }
+
+        match result {
+            Ok(result) if result.success => {
+                tracing::info!("Reconnected to {}", server_name);
+                emit_status(&app, &server_name, ReconnectStatus::Reconnected);
+                return;
+            }
+            Ok(result) => {
+                let cancelled = app
+                    .try_state::<Arc<ConnectionStateMachine>>()
+                    .is_some_and(|m| matches!(m.state(), ConnectionState::Cancelled { .. }));
+                if cancelled {
+                    emit_status(&app, &server_name, ReconnectStatus::Cancelled);
+                    return;
+                }
+
+                // Auth problems and competing connections won't go away by
+                // trying again
+                last_error = result.message;
+                break;
+            }
+            Err(e) => {
+                tracing::warn!("Reconnect attempt {} failed: {}", attempt, e);
+                last_error = e;
+            }
+        }
+    }
+
+    tracing::warn!("Giving up reconnecting to {}: {}", server_name, last_error);
+    reconnector.reset();
+    emit_status(
+        &app,
+        &server_name,
+        ReconnectStatus::GaveUp { error: last_error },
+    );
+}
+
+#[tauri::command]
+pub fn cancel_reconnect(app: AppHandle, state: tauri::State<'_, Arc<Reconnector>>) -> bool {
+    state.cancel(&app)
+}
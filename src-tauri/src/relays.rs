@@ -67,6 +67,14 @@ impl RelayState {
             .map(|r| r.relay.host.clone())
     }
 
+    pub async fn get_relay_host(&self, id: &str) -> Option<String> {
+        let relays = self.relays.read().await;
+        relays
+            .iter()
+            .find(|r| r.relay.id == id)
+            .map(|r| r.relay.host.clone())
+    }
+
     pub async fn all_relays_pinged(&self) -> bool {
         let relays = self.relays.read().await;
         relays.iter().all(|r| !r.checking)
@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::RwLock;
 
+use crate::byond::{deserialize_lenient, prefetch_server_versions, ByondVersion};
+use crate::settings::{load_settings, AppSettings};
+
 const SERVER_API_URL: &str = "https://db.cm-ss13.com/api/Round";
 const SERVER_FETCH_INTERVAL_SECS: u64 = 30;
 
@@ -24,7 +27,51 @@ pub struct Server {
     pub status: String,
     #[serde(default)]
     pub data: Option<ServerData>,
-    pub recommended_byond_version: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_lenient")]
+    pub recommended_byond_version: Option<ByondVersion>,
+    /// Oldest version the server accepts, when it accepts a range.
+    #[serde(default, deserialize_with = "deserialize_lenient")]
+    pub min_byond_version: Option<ByondVersion>,
+    /// Newest version the server accepts, when it accepts a range.
+    #[serde(default, deserialize_with = "deserialize_lenient")]
+    pub max_byond_version: Option<ByondVersion>,
+    /// Set from the player's settings, not by the API.
+    #[serde(default)]
+    pub is_favourite: bool,
+}
+
+impl Server {
+    pub fn has_version_range(&self) -> bool {
+        self.min_byond_version.is_some() || self.max_byond_version.is_some()
+    }
+
+    /// Whether `version` is inside the server's accepted range. Servers
+    /// without a range accept only their recommended version.
+    pub fn accepts_version(&self, version: ByondVersion) -> bool {
+        if !self.has_version_range() {
+            return self.recommended_byond_version == Some(version);
+        }
+
+        self.min_byond_version.is_none_or(|min| version >= min)
+            && self.max_byond_version.is_none_or(|max| version <= max)
+    }
+
+    /// The version to use from the server's range: the newest installed one
+    /// that fits, otherwise the recommended version if it fits, otherwise
+    /// the newest the server accepts.
+    pub fn version_in_range(&self, installed: &[ByondVersion]) -> Option<ByondVersion> {
+        let installed = installed
+            .iter()
+            .copied()
+            .filter(|version| self.accepts_version(*version))
+            .max();
+
+        installed
+            .or(self
+                .recommended_byond_version
+                .filter(|version| self.accepts_version(*version)))
+            .or(self.max_byond_version)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -74,6 +121,44 @@ async fn fetch_servers_internal() -> Result<Vec<Server>, String> {
     Ok(api_response.servers)
 }
 
+/// Apply the player's favourites and pinned order. Pinned servers come first
+/// in the order given, then the remaining favourites, then everything else
+/// in the order the API returned it.
+pub fn order_servers(mut servers: Vec<Server>, settings: &AppSettings) -> Vec<Server> {
+    for server in &mut servers {
+        server.is_favourite = settings.favourite_servers.contains(&server.name);
+    }
+
+    servers.sort_by_key(|server| {
+        let pinned = settings
+            .server_order
+            .iter()
+            .position(|name| *name == server.name)
+            .unwrap_or(usize::MAX);
+        (pinned, !server.is_favourite)
+    });
+    servers
+}
+
+fn ordered_for(handle: &AppHandle, servers: Vec<Server>) -> Vec<Server> {
+    match load_settings(handle) {
+        Ok(settings) => order_servers(servers, &settings),
+        Err(e) => {
+            tracing::warn!("Failed to load settings, servers left unordered: {}", e);
+            servers
+        }
+    }
+}
+
+/// Re-send the server list, e.g. after the player changes its order.
+pub async fn emit_servers_updated(handle: &AppHandle) {
+    let Some(state) = handle.try_state::<Arc<ServerState>>() else {
+        return;
+    };
+    let servers = ordered_for(handle, state.get_servers().await);
+    let _ = handle.emit("servers-updated", ServerUpdateEvent { servers });
+}
+
 /// Fetch servers and populate the cache. Called during app setup.
 pub async fn init_servers(state: &Arc<ServerState>) {
     match fetch_servers_internal().await {
@@ -88,8 +173,12 @@ pub async fn init_servers(state: &Arc<ServerState>) {
 }
 
 #[tauri::command]
-pub async fn get_servers(state: tauri::State<'_, Arc<ServerState>>) -> Result<Vec<Server>, String> {
-    Ok(state.servers.read().await.clone())
+pub async fn get_servers(
+    app: AppHandle,
+    state: tauri::State<'_, Arc<ServerState>>,
+) -> Result<Vec<Server>, String> {
+    let servers = state.servers.read().await.clone();
+    Ok(ordered_for(&app, servers))
 }
 
 pub async fn server_fetch_background_task(handle: AppHandle, state: Arc<ServerState>) {
@@ -99,6 +188,8 @@ pub async fn server_fetch_background_task(handle: AppHandle, state: Arc<ServerSt
         match fetch_servers_internal().await {
             Ok(servers) => {
                 *state.servers.write().await = servers.clone();
+                prefetch_server_versions(&handle, &servers).await;
+                let servers = ordered_for(&handle, servers);
                 let _ = handle.emit("servers-updated", ServerUpdateEvent { servers });
             }
             Err(error) => {
@@ -108,3 +199,76 @@ pub async fn server_fetch_background_task(handle: AppHandle, state: Arc<ServerSt
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(build: u32) -> ByondVersion {
+        ByondVersion { major: 516, build }
+    }
+
+    fn server(recommended: Option<u32>, min: Option<u32>, max: Option<u32>) -> Server {
+        Server {
+            name: "Test".to_string(),
+            url: "test:1400".to_string(),
+            status: "available".to_string(),
+            data: None,
+            recommended_byond_version: recommended.map(version),
+            min_byond_version: min.map(version),
+            max_byond_version: max.map(version),
+            is_favourite: false,
+        }
+    }
+
+    #[test]
+    fn accepts_only_recommended_without_range() {
+        let server = server(Some(1667), None, None);
+        assert!(server.accepts_version(version(1667)));
+        assert!(!server.accepts_version(version(1666)));
+    }
+
+    #[test]
+    fn accepts_versions_inside_range() {
+        let server = server(None, Some(1660), Some(1670));
+        assert!(server.accepts_version(version(1660)));
+        assert!(server.accepts_version(version(1670)));
+        assert!(!server.accepts_version(version(1659)));
+        assert!(!server.accepts_version(version(1671)));
+    }
+
+    #[test]
+    fn prefers_newest_installed_in_range() {
+        let server = server(Some(1670), Some(1660), Some(1670));
+        let installed = [version(1650), version(1662), version(1665), version(1680)];
+        assert_eq!(server.version_in_range(&installed), Some(version(1665)));
+    }
+
+    #[test]
+    fn falls_back_to_recommended_in_range() {
+        let server = server(Some(1667), Some(1660), Some(1670));
+        assert_eq!(
+            server.version_in_range(&[version(1650)]),
+            Some(version(1667))
+        );
+    }
+
+    #[test]
+    fn skips_recommended_outside_range() {
+        let server = server(Some(1680), Some(1660), Some(1670));
+        assert_eq!(server.version_in_range(&[]), Some(version(1670)));
+    }
+
+    #[test]
+    fn never_picks_the_oldest_accepted() {
+        assert_eq!(
+            server(None, Some(1660), Some(1670)).version_in_range(&[]),
+            Some(version(1670))
+        );
+        assert_eq!(server(None, Some(1660), None).version_in_range(&[]), None);
+        assert_eq!(
+            server(Some(1650), Some(1660), None).version_in_range(&[]),
+            None
+        );
+    }
+}
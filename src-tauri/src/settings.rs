@@ -1,8 +1,14 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tauri::{AppHandle, Manager};
 
+use crate::byond::{check_byond_version, ByondVersion, Prefetcher};
+use crate::reconnect::Reconnector;
+use crate::servers::{emit_servers_updated, ServerState};
+
 const SETTINGS_FILE: &str = "settings.json";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -22,11 +28,166 @@ pub enum Theme {
     Ntos,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WineRunner {
+    #[default]
+    Wine,
+    Proton,
+}
+
+/// How DreamSeeker is run on platforms without native BYOND support.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WineSettings {
+    #[serde(default)]
+    pub runner: WineRunner,
+    /// Path to the `wine` binary or `proton` script. Wine falls back to
+    /// `wine` on `PATH` when this is unset.
+    #[serde(default)]
+    pub binary: Option<String>,
+}
+
+fn default_keep_recent() -> usize {
+    3
+}
+
+/// Which installed BYOND versions survive pruning. Versions recommended by a
+/// server in the current list are always kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByondRetention {
+    /// Prune after each new install, not just when asked to.
+    #[serde(default)]
+    pub auto_prune: bool,
+    /// How many of the most recently launched versions to keep.
+    #[serde(default = "default_keep_recent")]
+    pub keep_recent: usize,
+}
+
+impl Default for ByondRetention {
+    fn default() -> Self {
+        Self {
+            auto_prune: false,
+            keep_recent: default_keep_recent(),
+        }
+    }
+}
+
+fn default_max_reconnect_attempts() -> u32 {
+    3
+}
+
+fn default_initial_reconnect_delay() -> u64 {
+    5
+}
+
+fn default_max_reconnect_delay() -> u64 {
+    60
+}
+
+/// Reconnecting to the last server when the game crashes. Delays double
+/// after each failed attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoReconnect {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_max_reconnect_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_reconnect_delay")]
+    pub initial_delay_secs: u64,
+    #[serde(default = "default_max_reconnect_delay")]
+    pub max_delay_secs: u64,
+}
+
+impl Default for AutoReconnect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: default_max_reconnect_attempts(),
+            initial_delay_secs: default_initial_reconnect_delay(),
+            max_delay_secs: default_max_reconnect_delay(),
+        }
+    }
+}
+
+/// A server the player added themselves. Kept apart from the official
+/// server list and connected to directly rather than through a relay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomServer {
+    pub name: String,
+    /// `host:port`
+    pub address: String,
+    pub byond_version: ByondVersion,
+    /// Login to send this server. `None`, the default, sends no token at all;
+    /// set it only for a server trusted with the player's account.
+    #[serde(default)]
+    pub auth_mode: Option<AuthMode>,
+}
+
+impl CustomServer {
+    pub fn host_and_port(&self) -> Result<(String, String), String> {
+        let address = self
+            .address
+            .trim()
+            .strip_prefix("byond://")
+            .unwrap_or(self.address.trim());
+
+        match address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+                Ok((host.to_string(), port.to_string()))
+            }
+            _ => Err(format!(
+                "Invalid address '{}'. Expected 'host:port'",
+                self.address
+            )),
+        }
+    }
+}
+
+/// A player's choice of BYOND version for one server, in place of the
+/// version the server recommends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "version", rename_all = "snake_case")]
+pub enum ByondVersionOverride {
+    /// Always use this version.
+    Pinned(ByondVersion),
+    /// Use the newest installed build with the recommended version's major.
+    NewestInMajor,
+}
+
+fn default_allow_unverified_byond() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     pub auth_mode: AuthMode,
     #[serde(default)]
     pub theme: Theme,
+    #[serde(default)]
+    pub wine: WineSettings,
+    #[serde(default)]
+    pub byond_retention: ByondRetention,
+    /// Install the BYOND versions servers recommend in the background, so
+    /// connecting doesn't have to wait for a download.
+    #[serde(default)]
+    pub prefetch_byond_versions: bool,
+    /// Install BYOND versions with no known hash, with a warning. On by
+    /// default until the hash manifest covers the versions servers use.
+    #[serde(default = "default_allow_unverified_byond")]
+    pub allow_unverified_byond: bool,
+    /// Version overrides keyed by server name.
+    #[serde(default)]
+    pub byond_version_overrides: HashMap<String, ByondVersionOverride>,
+    #[serde(default)]
+    pub auto_reconnect: AutoReconnect,
+    #[serde(default)]
+    pub custom_servers: Vec<CustomServer>,
+    /// Names of the servers the player has marked as favourites.
+    #[serde(default)]
+    pub favourite_servers: Vec<String>,
+    /// Server names to show first, in this order.
+    #[serde(default)]
+    pub server_order: Vec<String>,
 }
 
 impl Default for AppSettings {
@@ -35,6 +196,15 @@ impl Default for AppSettings {
         Self {
             auth_mode: AuthMode::Steam,
             theme: Theme::Default,
+            wine: WineSettings::default(),
+            byond_retention: ByondRetention::default(),
+            prefetch_byond_versions: false,
+            allow_unverified_byond: default_allow_unverified_byond(),
+            byond_version_overrides: HashMap::new(),
+            auto_reconnect: AutoReconnect::default(),
+            custom_servers: Vec::new(),
+            favourite_servers: Vec::new(),
+            server_order: Vec::new(),
         }
     }
 
@@ -43,6 +213,15 @@ impl Default for AppSettings {
         Self {
             auth_mode: AuthMode::CmSs13,
             theme: Theme::Default,
+            wine: WineSettings::default(),
+            byond_retention: ByondRetention::default(),
+            prefetch_byond_versions: false,
+            allow_unverified_byond: default_allow_unverified_byond(),
+            byond_version_overrides: HashMap::new(),
+            auto_reconnect: AutoReconnect::default(),
+            custom_servers: Vec::new(),
+            favourite_servers: Vec::new(),
+            server_order: Vec::new(),
         }
     }
 }
@@ -119,3 +298,192 @@ pub async fn set_theme(app: AppHandle, theme: Theme) -> Result<AppSettings, Stri
     save_settings(&app, &settings)?;
     Ok(settings)
 }
+
+#[tauri::command]
+pub async fn set_wine_settings(app: AppHandle, wine: WineSettings) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.wine = wine;
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_byond_retention(
+    app: AppHandle,
+    retention: ByondRetention,
+) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.byond_retention = retention;
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_prefetch_byond_versions(
+    app: AppHandle,
+    enabled: bool,
+) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.prefetch_byond_versions = enabled;
+    save_settings(&app, &settings)?;
+
+    if !enabled {
+        if let Some(prefetcher) = app.try_state::<Arc<Prefetcher>>() {
+            prefetcher.cancel();
+        }
+    }
+
+    Ok(settings)
+}
+
+/// Set or clear a server's version override. A pinned version must already
+/// be installed, so a typo can't turn into a download on the next connect.
+#[tauri::command]
+pub async fn set_byond_version_override(
+    app: AppHandle,
+    server_name: String,
+    version_override: Option<ByondVersionOverride>,
+) -> Result<AppSettings, String> {
+    if let Some(ByondVersionOverride::Pinned(version)) = version_override {
+        if !check_byond_version(app.clone(), version).await?.installed {
+            return Err(format!(
+                "BYOND {} is not installed. Install or import it before pinning it",
+                version
+            ));
+        }
+    }
+
+    let mut settings = load_settings(&app)?;
+    match version_override {
+        Some(version_override) => {
+            settings
+                .byond_version_overrides
+                .insert(server_name, version_override);
+        }
+        None => {
+            settings.byond_version_overrides.remove(&server_name);
+        }
+    }
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_auto_reconnect(
+    app: AppHandle,
+    auto_reconnect: AutoReconnect,
+) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    let enabled = auto_reconnect.enabled;
+    settings.auto_reconnect = auto_reconnect;
+    save_settings(&app, &settings)?;
+
+    if !enabled {
+        if let Some(reconnector) = app.try_state::<Arc<Reconnector>>() {
+            reconnector.cancel(&app);
+        }
+    }
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn list_custom_servers(app: AppHandle) -> Result<Vec<CustomServer>, String> {
+    Ok(load_settings(&app)?.custom_servers)
+}
+
+/// Add a custom server, replacing any existing one with the same name.
+/// Custom servers are sent no login token unless `auth_mode` is set, as the
+/// token would let whoever runs the server act as the player.
+#[tauri::command]
+pub async fn add_custom_server(
+    app: AppHandle,
+    server: CustomServer,
+) -> Result<AppSettings, String> {
+    let server = CustomServer {
+        name: server.name.trim().to_string(),
+        ..server
+    };
+    if server.name.is_empty() {
+        return Err("Custom server name cannot be empty".to_string());
+    }
+    server.host_and_port()?;
+
+    if let Some(server_state) = app.try_state::<Arc<ServerState>>() {
+        let official = server_state.get_servers().await;
+        if official
+            .iter()
+            .any(|s| s.name.eq_ignore_ascii_case(&server.name))
+        {
+            return Err(format!(
+                "'{}' is the name of an official server",
+                server.name
+            ));
+        }
+    }
+
+    if let Some(auth_mode) = server.auth_mode {
+        tracing::warn!(
+            "Custom server '{}' at {} will be sent the player's {:?} login",
+            server.name,
+            server.address,
+            auth_mode
+        );
+    }
+
+    let mut settings = load_settings(&app)?;
+    settings.custom_servers.retain(|s| s.name != server.name);
+    settings.custom_servers.push(server);
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn remove_custom_server(app: AppHandle, name: String) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.custom_servers.retain(|s| s.name != name);
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_server_favourite(
+    app: AppHandle,
+    server_name: String,
+    favourite: bool,
+) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings
+        .favourite_servers
+        .retain(|name| *name != server_name);
+    if favourite {
+        settings.favourite_servers.push(server_name);
+    }
+    save_settings(&app, &settings)?;
+
+    emit_servers_updated(&app).await;
+    Ok(settings)
+}
+
+/// Pin servers to the top of the list in the given order. An empty list
+/// restores the default order.
+#[tauri::command]
+pub async fn set_server_order(app: AppHandle, order: Vec<String>) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.server_order = order;
+    save_settings(&app, &settings)?;
+
+    emit_servers_updated(&app).await;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn set_allow_unverified_byond(
+    app: AppHandle,
+    allowed: bool,
+) -> Result<AppSettings, String> {
+    let mut settings = load_settings(&app)?;
+    settings.allow_unverified_byond = allowed;
+    save_settings(&app, &settings)?;
+    Ok(settings)
+}
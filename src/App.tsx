@@ -33,6 +33,7 @@ interface AutoConnectEvent {
     | "steam_linking_required"
     | "connecting"
     | "connected"
+    | "cancelled"
     | "error";
   server_name: string;
   message: string | null;
@@ -97,18 +98,22 @@ function AppContent() {
     setAuthMode,
     theme,
     devMode,
+    allowUnverifiedByond,
     load: loadSettings,
     saveAuthMode,
     saveTheme,
+    saveAllowUnverifiedByond,
   } = useSettingsStore(
     useShallow((s) => ({
       authMode: s.authMode,
       setAuthMode: s.setAuthMode,
       theme: s.theme,
       devMode: s.devMode,
+      allowUnverifiedByond: s.allowUnverifiedByond,
       load: s.load,
       saveAuthMode: s.saveAuthMode,
       saveTheme: s.saveTheme,
+      saveAllowUnverifiedByond: s.saveAllowUnverifiedByond,
     })),
   );
 
@@ -217,6 +222,7 @@ function AppContent() {
               break;
 
             case "connected":
+            case "cancelled":
               setAutoConnecting(false);
               break;
           }
@@ -348,6 +354,17 @@ function AppContent() {
     [saveTheme, showError],
   );
 
+  const handleAllowUnverifiedByondChange = useCallback(
+    async (allowed: boolean) => {
+      try {
+        await saveAllowUnverifiedByond(allowed);
+      } catch (err) {
+        showError(err instanceof Error ? err.message : String(err));
+      }
+    },
+    [saveAllowUnverifiedByond, showError],
+  );
+
   // Relay handlers
   const handleRelaySelect = useCallback(
     (relayId: string) => {
@@ -382,8 +399,10 @@ function AppContent() {
         theme={theme}
         steamAvailable={steamAvailable}
         devMode={devMode}
+        allowUnverifiedByond={allowUnverifiedByond}
         onAuthModeChange={handleAuthModeChange}
         onThemeChange={handleThemeChange}
+        onAllowUnverifiedByondChange={handleAllowUnverifiedByondChange}
         onLoginRequired={onLoginRequired}
         onSteamAuthRequired={onSteamAuthRequired}
         onClose={() => setSettingsVisible(false)}
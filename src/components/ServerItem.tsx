@@ -1,8 +1,13 @@
-import { useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
 import { GAME_STATES } from "../constants";
 import { useConnect, useError } from "../hooks";
 import { useServerStore } from "../stores";
-import type { Server } from "../types";
+import type {
+  ByondInstallProgress,
+  ConnectionState,
+  Server,
+} from "../types";
 import { formatDuration } from "../utils";
 
 interface ServerItemProps {
@@ -19,11 +24,51 @@ export function ServerItem({
   autoConnecting = false,
 }: ServerItemProps) {
   const [connecting, setConnecting] = useState(false);
+  const [installProgress, setInstallProgress] =
+    useState<ByondInstallProgress | null>(null);
   const { showError } = useError();
   const { connect } = useConnect();
 
   const relaysReady = useServerStore((s) => s.relaysReady);
 
+  useEffect(() => {
+    if (!connecting && !autoConnecting) {
+      setInstallProgress(null);
+      return;
+    }
+
+    // The version being installed can differ from the recommended one, e.g.
+    // a pinned override or an installed version that fits the server's range
+    let installingVersion: string | null = null;
+
+    const unlistenState = listen<ConnectionState>(
+      "connection-state",
+      (event) => {
+        const state = event.payload;
+        if (
+          state.state === "installing" &&
+          state.server_name === server.name
+        ) {
+          installingVersion = state.version;
+        }
+      },
+    );
+
+    const unlistenProgress = listen<ByondInstallProgress>(
+      "byond-install-progress",
+      (event) => {
+        if (event.payload.version === installingVersion) {
+          setInstallProgress(event.payload);
+        }
+      },
+    );
+
+    return () => {
+      unlistenState.then((fn) => fn());
+      unlistenProgress.then((fn) => fn());
+    };
+  }, [connecting, autoConnecting, server.name]);
+
   const isOnline = server.status === "available";
   const data = server.data;
 
@@ -53,6 +98,17 @@ export function ServerItem({
 
   const canConnect = isOnline && relaysReady;
 
+  const getConnectLabel = () => {
+    if (!connecting && !autoConnecting) return "Connect";
+    if (!installProgress) return "Connecting...";
+    if (installProgress.phase === "extract") return "Extracting...";
+    if (!installProgress.total_bytes) return "Downloading...";
+    const percent = Math.floor(
+      (installProgress.downloaded_bytes / installProgress.total_bytes) * 100,
+    );
+    return `Downloading ${percent}%`;
+  };
+
   return (
     <div className="server-item">
       <div className="server-info">
@@ -82,7 +138,7 @@ export function ServerItem({
           onClick={handleConnect}
           disabled={!canConnect || connecting || autoConnecting}
         >
-          {connecting || autoConnecting ? "Connecting..." : "Connect"}
+          {getConnectLabel()}
         </button>
       </div>
     </div>
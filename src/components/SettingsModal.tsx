@@ -157,8 +157,10 @@ interface SettingsModalProps {
   theme: Theme;
   steamAvailable: boolean;
   devMode: boolean;
+  allowUnverifiedByond: boolean;
   onAuthModeChange: (mode: AuthMode) => void;
   onThemeChange: (theme: Theme) => void;
+  onAllowUnverifiedByondChange: (allowed: boolean) => void;
   onLoginRequired: () => void;
   onSteamAuthRequired: () => void;
   onClose: () => void;
@@ -170,8 +172,10 @@ export function SettingsModal({
   theme,
   steamAvailable,
   devMode,
+  allowUnverifiedByond,
   onAuthModeChange,
   onThemeChange,
+  onAllowUnverifiedByondChange,
   onLoginRequired,
   onSteamAuthRequired,
   onClose,
@@ -272,6 +276,32 @@ export function SettingsModal({
           </div>
         </div>
 
+        <div className="settings-section">
+          <h3>BYOND Downloads</h3>
+          <p className="settings-description">
+            Downloads are checked against known hashes before installing. New
+            BYOND builds may not be listed yet.
+          </p>
+          <label
+            className={`settings-toggle ${allowUnverifiedByond ? "selected" : ""}`}
+          >
+            <input
+              type="checkbox"
+              checked={allowUnverifiedByond}
+              onChange={(e) => onAllowUnverifiedByondChange(e.target.checked)}
+            />
+            <div className="auth-mode-info">
+              <span className="auth-mode-name">
+                Allow unverified BYOND versions
+              </span>
+              <span className="auth-mode-desc">
+                Install versions with no known hash. Turning this off refuses
+                them, which can stop you connecting to servers on new builds.
+              </span>
+            </div>
+          </label>
+        </div>
+
         {devMode && (
           <div className="settings-section dev-section">
             <h3>Developer Options</h3>
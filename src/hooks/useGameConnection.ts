@@ -1,6 +1,7 @@
 import { listen } from "@tauri-apps/api/event";
 import { useCallback, useEffect, useState } from "react";
 import type { GameConnectionState } from "../components";
+import type { ConnectionState, GameExit } from "../types";
 
 interface GameRestartingEvent {
   server_name: string;
@@ -37,17 +38,29 @@ export function useGameConnection() {
       }
     );
 
-    const unlistenClosed = listen("game-closed", () => {
+    const unlistenExited = listen<GameExit>("game-exited", () => {
       setGameConnectionState("idle");
       setConnectedServerName(null);
       setRestartReason(null);
     });
 
+    const unlistenState = listen<ConnectionState>(
+      "connection-state",
+      (event) => {
+        if (event.payload.state === "cancelled") {
+          setGameConnectionState("idle");
+          setConnectedServerName(null);
+          setRestartReason(null);
+        }
+      }
+    );
+
     return () => {
       unlistenConnecting.then((unlisten) => unlisten());
       unlistenConnected.then((unlisten) => unlisten());
       unlistenRestarting.then((unlisten) => unlisten());
-      unlistenClosed.then((unlisten) => unlisten());
+      unlistenExited.then((unlisten) => unlisten());
+      unlistenState.then((unlisten) => unlisten());
     };
   }, []);
 
@@ -6,18 +6,21 @@ interface SettingsStore {
   authMode: AuthMode;
   theme: Theme;
   devMode: boolean;
+  allowUnverifiedByond: boolean;
 
   setAuthMode: (mode: AuthMode) => void;
   setTheme: (theme: Theme) => void;
   load: () => Promise<AppSettings | null>;
   saveAuthMode: (mode: AuthMode) => Promise<void>;
   saveTheme: (theme: Theme) => Promise<void>;
+  saveAllowUnverifiedByond: (allowed: boolean) => Promise<void>;
 }
 
 export const useSettingsStore = create<SettingsStore>()((set) => ({
   authMode: "cm_ss13",
   theme: "default",
   devMode: false,
+  allowUnverifiedByond: true,
 
   setAuthMode: (authMode) => set({ authMode }),
   setTheme: (theme) => set({ theme }),
@@ -28,7 +31,12 @@ export const useSettingsStore = create<SettingsStore>()((set) => ({
         invoke<AppSettings>("get_settings"),
         invoke<boolean>("is_dev_mode"),
       ]);
-      set({ authMode: settings.auth_mode, theme: settings.theme, devMode });
+      set({
+        authMode: settings.auth_mode,
+        theme: settings.theme,
+        devMode,
+        allowUnverifiedByond: settings.allow_unverified_byond,
+      });
       return settings;
     } catch (err) {
       console.error("Failed to load settings:", err);
@@ -45,4 +53,9 @@ export const useSettingsStore = create<SettingsStore>()((set) => ({
     await invoke<AppSettings>("set_theme", { theme });
     set({ theme });
   },
+
+  saveAllowUnverifiedByond: async (allowed: boolean) => {
+    await invoke<AppSettings>("set_allow_unverified_byond", { allowed });
+    set({ allowUnverifiedByond: allowed });
+  },
 }));
@@ -44,11 +44,153 @@ export interface SteamAuthState {
   error: string | null;
 }
 
+export type WineRunner = "wine" | "proton";
+
+export interface WineSettings {
+  runner: WineRunner;
+  binary: string | null;
+}
+
+export type WinePrefixStatus = "missing" | "ready" | "degraded" | "broken";
+
+export interface WinePrefixHealth {
+  status: WinePrefixStatus;
+  path: string;
+  runner: WineRunner;
+  initialized: boolean;
+  overrides_applied: boolean;
+  webview2_installed: boolean;
+  fonts_installed: boolean;
+  updated_at: string | null;
+  error: string | null;
+}
+
+export interface ByondRetention {
+  auto_prune: boolean;
+  keep_recent: number;
+}
+
+export interface ByondVersionInfo {
+  version: string;
+  installed: boolean;
+  path: string | null;
+}
+
+export interface InstalledByondVersion extends ByondVersionInfo {
+  size_bytes: number;
+  installed_at: string | null;
+  last_used_at: string | null;
+  recommended: boolean;
+}
+
+export interface ByondDiskUsage {
+  versions: InstalledByondVersion[];
+  total_bytes: number;
+  webview2_data_bytes: number;
+}
+
+export interface ByondMajorUpdate {
+  major: number;
+  installed: string | null;
+  latest: string;
+  update_available: boolean;
+}
+
+export interface LatestByondVersions {
+  stable: string;
+  beta: string | null;
+  fetched_at: string;
+  majors: ByondMajorUpdate[];
+}
+
+export interface PruneResult {
+  removed: string[];
+  freed_bytes: number;
+}
+
+export type ByondVersionOverride =
+  | { kind: "pinned"; version: string }
+  | { kind: "newest_in_major" };
+
+export interface AutoReconnect {
+  enabled: boolean;
+  max_attempts: number;
+  initial_delay_secs: number;
+  max_delay_secs: number;
+}
+
+export interface CustomServer {
+  name: string;
+  address: string;
+  byond_version: string;
+  auth_mode: AuthMode | null;
+}
+
 export interface AppSettings {
   auth_mode: AuthMode;
   theme: Theme;
+  wine: WineSettings;
+  byond_retention: ByondRetention;
+  prefetch_byond_versions: boolean;
+  allow_unverified_byond: boolean;
+  byond_version_overrides: Record<string, ByondVersionOverride>;
+  auto_reconnect: AutoReconnect;
+  custom_servers: CustomServer[];
+  favourite_servers: string[];
+  server_order: string[];
+}
+
+export type ConnectionState =
+  | { state: "idle" }
+  | { state: "authenticating"; server_name: string }
+  | { state: "installing"; server_name: string; version: string }
+  | { state: "launching"; server_name: string; version: string }
+  | { state: "connected"; server_name: string }
+  | { state: "failed"; server_name: string; error: string }
+  | { state: "cancelled"; server_name: string };
+
+export type ExitKind = "normal" | "killed" | "crashed";
+
+export interface GameExit {
+  server_name: string | null;
+  kind: ExitKind;
+  exit_code: number | null;
+  duration_secs: number;
+  stderr: string;
+}
+
+export interface SessionRecord {
+  server_name: string;
+  host: string;
+  byond_version: string;
+  auth_mode: string | null;
+  started_at: string;
+  ended_at: string;
+  exit_kind: ExitKind | null;
+  exit_code: number | null;
+}
+
+export interface ServerPlaytime {
+  server_name: string;
+  total_secs: number;
+  sessions: number;
+  last_played: string;
 }
 
+export type ReconnectStatus =
+  | {
+      status: "waiting";
+      attempt: number;
+      max_attempts: number;
+      delay_secs: number;
+    }
+  | { status: "connecting"; attempt: number; max_attempts: number }
+  | { status: "reconnected" }
+  | { status: "gave_up"; error: string }
+  | { status: "cancelled" };
+
+export type ReconnectEvent = ReconnectStatus & { server_name: string };
+
 export interface ErrorNotification {
   id: number;
   message: string;
@@ -80,4 +222,16 @@ export interface Server {
   status: string;
   data?: ServerData;
   recommended_byond_version?: string;
+  min_byond_version?: string;
+  max_byond_version?: string;
+  is_favourite: boolean;
+}
+
+export interface ByondInstallProgress {
+  version: string;
+  phase: "download" | "extract";
+  downloaded_bytes: number;
+  total_bytes: number | null;
+  files_extracted: number;
+  total_files: number;
 }
@@ -0,0 +1,45 @@
+#!/bin/bash
+set -e
+
+if [ -z "$1" ]; then
+    echo "Usage: $0 <byond version>..."
+    echo "Example: $0 516.1667 515.1647"
+    exit 1
+fi
+
+HASHES_FILE="src-tauri/byond_hashes.json"
+TEMP_DIR=$(mktemp -d)
+trap 'rm -rf "$TEMP_DIR"' EXIT
+
+for VERSION in "$@"; do
+    if ! [[ "$VERSION" =~ ^[1-9][0-9]*\.[1-9][0-9]*$ ]]; then
+        echo "Error: $VERSION is not a BYOND version (e.g., 516.1667)"
+        exit 1
+    fi
+
+    MAJOR="${VERSION%%.*}"
+    URL="https://www.byond.com/download/build/$MAJOR/${VERSION}_byond.zip"
+    ARCHIVE="$TEMP_DIR/${VERSION}_byond.zip"
+
+    echo "Downloading $URL..."
+    curl --fail --location --silent --show-error --output "$ARCHIVE" "$URL"
+
+    HASH=$(sha256sum "$ARCHIVE" | cut -d' ' -f1)
+    echo "$VERSION: $HASH"
+
+    python3 - "$HASHES_FILE" "$VERSION" "$HASH" <<'PY'
+import json
+import sys
+
+path, version, digest = sys.argv[1:]
+with open(path) as f:
+    hashes = json.load(f)
+
+hashes[version] = digest
+with open(path, "w") as f:
+    json.dump(dict(sorted(hashes.items())), f, indent=2)
+    f.write("\n")
+PY
+done
+
+echo "Updated $HASHES_FILE"